@@ -0,0 +1,79 @@
+//! Parsing of the command line arguments.
+//!
+//! # Args
+//!
+//! Contains the [`Args`] given to cli-boids on the command line. Every option
+//! takes the form of a flag followed by a single value:
+//! - `--paths <FILE>`: Loads the paths followed by the groups of boids, see
+//!   [`Path::load`](crate::boids::Path::load).
+//! - `--affinity <FILE>`: Loads the affinity between the groups of boids, see
+//!   [`AffinityMatrix::load`](crate::boids::affinity::AffinityMatrix::load).
+//! - `--text <TEXT>`: Spells out the text with the boids, see
+//!   [`Bitmap::from_text`](crate::boids::formation::Bitmap::from_text).
+//! - `--image <FILE>`: Forms the PBM image with the boids instead of the text,
+//!   see [`Bitmap::load_pbm`](crate::boids::formation::Bitmap::load_pbm).
+//! - `--utc-offset <HOURS>`: Shows the time at the offset from UTC on the clock
+//!   instead of the local time, see
+//!   [`Clock::new`](crate::boids::clock::Clock::new).
+
+use std::io::{Error, ErrorKind, Result};
+
+/// The options given on the command line.
+#[derive(Default)]
+pub struct Args {
+    /// The file containing the paths for the boids to follow.
+    pub paths: Option<String>,
+    /// The file containing the affinity matrix between the groups.
+    pub affinity: Option<String>,
+    /// The text spelled out by the boids.
+    pub text: Option<String>,
+    /// The PBM image formed by the boids.
+    pub image: Option<String>,
+    /// The offset from UTC in hours of the time shown by the clock, overriding
+    /// the local time.
+    pub utc_offset: Option<f32>,
+}
+
+impl Args {
+    /// Parses the options from the `args`, excluding the name of the program.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it encounters an unknown option,
+    /// an option without its value or a number that can not be parsed.
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Args> {
+        let mut parsed = Args::default();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next().ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("missing value for `{}`", arg),
+                    )
+                })
+            };
+            match arg.as_str() {
+                "--paths" => parsed.paths = Some(value()?),
+                "--affinity" => parsed.affinity = Some(value()?),
+                "--text" => parsed.text = Some(value()?),
+                "--image" => parsed.image = Some(value()?),
+                "--utc-offset" => {
+                    let value = value()?;
+                    parsed.utc_offset = Some(value.parse().map_err(|_| {
+                        Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("invalid number `{}` for `{}`", value, arg),
+                        )
+                    })?);
+                }
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("unknown option `{}`", arg),
+                    ));
+                }
+            }
+        }
+        Ok(parsed)
+    }
+}
@@ -7,7 +7,43 @@
 //! ## Simulating
 //! The simulation is run by progressively stepping through time using `update_boids`.
 //! This function updates the positions and velocities of the boids based on the basic
-//! boid rules and the given settings.
+//! boid rules and the given settings. Depending on the [`UpdateMode`], the boids
+//! are either updated one by one or all at once from the same snapshot, where
+//! [`compare_update_modes`] measures how much both modes differ.
+//!
+//! ## Ecosystem
+//! When the ecosystem is enabled, boids age and spend energy over time, which
+//! they slowly regain anywhere and quickly at the food sources and attractors
+//! in the [`Environment`]. Boids with enough energy give birth to offspring of their
+//! own kind near themselves, while boids without energy die.
+//!
+//! ## Noise
+//! Besides white noise drawn anew every update, the boids can wander along a
+//! smoothly drifting angle, follow Ornstein-Uhlenbeck noise or take Lévy
+//! flight bursts, see [`NoiseModel`]. These coherent models keep their state
+//! in the [`NoiseState`] of every boid.
+//!
+//! ## Kernels
+//! The separation, cohesion and alignment each weigh their neighbours by
+//! distance through a [`Kernel`], either uniformly up to a hard cut-off,
+//! growing towards close neighbours, or fading out towards the edge of their
+//! range such that neighbours do not pop in and out.
+//!
+//! ## Formations
+//! The [`Environment`] can hold a [`Formation`](formation::Formation) of text
+//! or an image, towards which every boid is pulled until it is released.
+//! The [`Clock`](clock::Clock) keeps such a formation showing the current
+//! time, re-assigning the targets to the nearby boids whenever it changes.
+//!
+//! ## Perching
+//! When perching is enabled, prey occasionally land on the bottom border or on
+//! top of painted walls, resting there until their time is up or until they
+//! are disturbed by the mouse or by flock-mates flying overhead.
+//!
+//! ## 3D
+//! Besides the regular simulation on the plane of the screen, the [`Flock3D`]
+//! simulates a separate flock within a 3D volume, following the same basic
+//! rules.
 //!
 //! ## BoidSettings
 //! To control the simulation, the `BoidSettings` struct must be created. This struct controls
@@ -29,28 +65,123 @@
 //! // Create population
 //! let population = populate(COUNT, GROUP_COUNT, &boid_settings);
 //! // Update one time step
-//! update_boids(&mut population, &boid_settings, DELTA_TIME);
+//! let environment = Environment::new(10, 10);
+//! update_boids(&mut population, &boid_settings, &environment, DELTA_TIME);
 //! ```
 
 use crate::grid::{Grid, ValueNode};
 use crate::vector2::Vector2;
-pub use settings::{BoidSettings, BorderSettings};
+pub use attractors::Attractor;
+pub use environment::Environment;
+pub use flock3d::Flock3D;
+pub use food::FoodSource;
+pub use obstacles::Obstacle;
+pub use paths::Path;
+pub use settings::{
+    BoidSettings, BorderSettings, Integrator, Kernel, MouseTool, NeighbourMode, NeighbourSampling,
+    NoiseModel, UpdateMode,
+};
 
+pub mod affinity;
+pub mod arena;
+pub mod attractors;
+pub mod clock;
+pub mod environment;
+pub mod flock3d;
+pub mod flow;
+pub mod food;
+pub mod formation;
+pub mod obstacles;
+pub mod paths;
 pub mod settings;
 pub mod simulation;
+pub mod walls;
 
 /// The amount of cells that must be checked in any direction to cover the entire visible area of a boid
 pub const CELLS_IN_RADIUS: i32 = 2;
-/// The maximum amount of samples a boid can use to estimate its velocity
+/// The default sample budget of the boids when sampling their neighbours
 pub const MAX_SAMPLES: i32 = 300;
+/// The energy of a newly populated or spawned boid
+pub const INITIAL_ENERGY: f32 = 1.0;
+/// How far from its parent an offspring is born
+const BIRTH_RADIUS: f32 = 1.0;
+/// The most boids the simulation holds, limiting both spawning and the growth
+/// of the ecosystem
+pub const MAX_POPULATION: usize = 20000;
+
+/// The role a boid plays in the simulation.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BoidKind {
+    /// Regular flocking boid, flees from predators within its visible range.
+    Prey,
+    /// Hunts the prey by steering towards the densest nearby group of prey.
+    Predator,
+}
+
+/// Whether a boid is flying or resting on a surface.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FlightState {
+    /// Regular flying boid.
+    Flying,
+    /// Resting on a surface for the `remaining` time, during which the boid
+    /// is not moved.
+    Perched { remaining: f32 },
+}
+
+/// The state of the random noise of a single boid, kept between updates by
+/// the coherent noise models.
+#[derive(Debug, Copy, Clone)]
+pub struct NoiseState {
+    /// The angle in radians of the sideways push of the wander noise,
+    /// relative to the heading of the boid.
+    pub wander_angle: f32,
+    /// The current force of the Ornstein-Uhlenbeck and Lévy flight noise.
+    pub force: Vector2,
+}
+
+impl Default for NoiseState {
+    fn default() -> NoiseState {
+        NoiseState {
+            wander_angle: 0.0,
+            force: Vector2::ZERO,
+        }
+    }
+}
+
+impl NoiseState {
+    /// Creates a new [`NoiseState`] with a random wander angle, such that
+    /// boids do not all start wandering to the same side.
+    pub fn random() -> NoiseState {
+        NoiseState {
+            wander_angle: fastrand::f32() * std::f32::consts::TAU,
+            force: Vector2::ZERO,
+        }
+    }
+}
 
 /// Simple representation of a boid
 #[derive(Debug, Copy, Clone)]
 pub struct Boid {
     pub position: Vector2,
     pub velocity: Vector2,
-    /// Group index, the boid is only attracted by and aligning with other boids of the same group
+    /// The position before the last update, used to interpolate between
+    /// updates when rendering
+    pub previous_position: Vector2,
+    /// Group index, the boid's attraction to and alignment with other boids
+    /// depends on the affinity between their groups
     group: u8,
+    /// Whether the boid is prey or a predator
+    pub kind: BoidKind,
+    /// Index of the next waypoint on the path of the boid's group
+    pub waypoint: usize,
+    /// The time the boid has been alive
+    pub age: f32,
+    /// The energy of the boid, which it needs to stay alive and reproduce
+    pub energy: f32,
+    /// Whether the boid is flying or perched
+    pub flight: FlightState,
+    /// The state of the random noise on the movement of the boid
+    pub noise: NoiseState,
 }
 
 impl Boid {
@@ -58,7 +189,14 @@ impl Boid {
         Boid {
             position,
             velocity,
+            previous_position: position,
             group,
+            kind: BoidKind::Prey,
+            waypoint: 0,
+            age: 0.0,
+            energy: INITIAL_ENERGY,
+            flight: FlightState::Flying,
+            noise: NoiseState::random(),
         }
     }
 }
@@ -78,19 +216,20 @@ fn get_grid_position(
 }
 
 /// Initialises a new grid according to the defined number of cells within the
-/// affecting radius of a boid and width and height in the `boid_settings`.
+/// largest affecting radius of any group and width and height in the `boid_settings`.
+/// The rows and columns of the grid wrap around along with the borders.
 fn grid_init(count: usize, boid_settings: &BoidSettings) -> Grid<Boid> {
-    let grid_columns = ((CELLS_IN_RADIUS as f32 * boid_settings.width as f32
-        / boid_settings
-            .visible_range
-            .max(boid_settings.protected_range)) as usize)
-        .max(1);
-    let grid_rows = ((CELLS_IN_RADIUS as f32 * boid_settings.height as f32
-        / boid_settings
-            .visible_range
-            .max(boid_settings.protected_range)) as usize)
-        .max(1);
-    Grid::new(count, grid_columns, grid_rows)
+    let range = boid_settings.max_range();
+    let grid_columns =
+        ((CELLS_IN_RADIUS as f32 * boid_settings.width as f32 / range) as usize).max(1);
+    let grid_rows =
+        ((CELLS_IN_RADIUS as f32 * boid_settings.height as f32 / range) as usize).max(1);
+    let mut grid = Grid::new(count, grid_columns, grid_rows);
+    grid.set_wrapping(
+        boid_settings.border_settings.wraps_vertically(),
+        boid_settings.border_settings.wraps_horizontally(),
+    );
+    grid
 }
 
 /// Creates a new population of `count` number boids divided equally among
@@ -99,7 +238,8 @@ fn grid_init(count: usize, boid_settings: &BoidSettings) -> Grid<Boid> {
 /// within any direction of the boids visual range.
 ///
 /// ## Groups
-/// Boids belonging to a certain group are only attracted to others in the same group.
+/// How strongly boids belonging to a certain group are attracted to others
+/// depends on the affinity between the groups in the `boid_settings`.
 pub fn populate(count: usize, group_count: u8, boid_settings: &BoidSettings) -> Grid<Boid> {
     let mut generator = fastrand::Rng::new();
     let mut grid = grid_init(count, boid_settings);
@@ -120,9 +260,136 @@ pub fn populate(count: usize, group_count: u8, boid_settings: &BoidSettings) ->
             grid_column,
         );
     }
+    assign_predators(&mut grid, boid_settings.predator_count);
     grid
 }
 
+/// Adds a new boid at `position` with the given `velocity` to the `grid`,
+/// unless the grid already holds [`MAX_POPULATION`] boids. The boid joins the
+/// group that continues the equal division of the boids among the groups in
+/// the `boid_settings`.
+///
+/// # Return
+/// Returns `true` if the boid was added.
+pub fn spawn_boid(
+    grid: &mut Grid<Boid>,
+    boid_settings: &BoidSettings,
+    position: Vector2,
+    velocity: Vector2,
+) -> bool {
+    if grid.count >= MAX_POPULATION {
+        return false;
+    }
+    let position = clamp_to_screen(position, boid_settings);
+    let group = (grid.count % boid_settings.group_count() as usize) as u8;
+    let (grid_row, grid_column) = get_grid_position(position, boid_settings, grid);
+    grid.add_val(Boid::new(position, velocity, group), grid_row, grid_column);
+    true
+}
+
+/// Returns the `position` clamped to the screen given by the `boid_settings`,
+/// excluding its right and bottom edges.
+#[inline]
+fn clamp_to_screen(position: Vector2, boid_settings: &BoidSettings) -> Vector2 {
+    Vector2::new(
+        position
+            .x
+            .clamp(0.0, (boid_settings.width as f32).next_down()),
+        position
+            .y
+            .clamp(0.0, (boid_settings.height as f32).next_down()),
+    )
+}
+
+/// Removes and returns the boid at `index` in the `grid`, where the last boid
+/// takes its place, see [`Grid::remove_val`]. As predators may be removed,
+/// the predator count must be synchronised afterwards, see
+/// [`BoidSettings::sync_predator_count`].
+pub fn remove_boid(grid: &mut Grid<Boid>, boid_settings: &BoidSettings, index: usize) -> Boid {
+    let cell = get_grid_position(grid.values[index].val.position, boid_settings, grid);
+    let last_cell = get_grid_position(
+        grid.values[grid.count - 1].val.position,
+        boid_settings,
+        grid,
+    );
+    grid.remove_val(index, cell, last_cell)
+}
+
+/// Removes at most `limit` boids within `radius` of the `position` from the
+/// `grid`, returning the amount of removed boids.
+pub fn remove_boids_within(
+    grid: &mut Grid<Boid>,
+    boid_settings: &BoidSettings,
+    position: Vector2,
+    radius: f32,
+    limit: usize,
+) -> usize {
+    let count = grid.count;
+    // Iterate backwards, as removed boids are replaced by already checked ones.
+    for index in (0..count).rev() {
+        if count - grid.count >= limit {
+            break;
+        }
+        if (grid.values[index].val.position - position).sqr_magnitude() < radius * radius {
+            remove_boid(grid, boid_settings, index);
+        }
+    }
+    count - grid.count
+}
+
+/// Changes the amount of boids in the `grid` to `count`, at most
+/// [`MAX_POPULATION`], either by removing the last boids or by adding boids at
+/// random positions. The predator count in the `boid_settings` is kept in sync
+/// with the removed boids.
+pub fn set_boid_count(grid: &mut Grid<Boid>, boid_settings: &mut BoidSettings, count: usize) {
+    let count = count.min(MAX_POPULATION);
+    while grid.count > count {
+        remove_boid(grid, boid_settings, grid.count - 1);
+    }
+    boid_settings.sync_predator_count(grid);
+    let width = boid_settings.width as f32;
+    let height = boid_settings.height as f32;
+    while grid.count < count {
+        let position = Vector2::new(fastrand::f32() * width, fastrand::f32() * height);
+        spawn_boid(grid, boid_settings, position, Vector2::ZERO);
+    }
+}
+
+/// Divides the boids in the `grid` equally among `group_count` groups.
+fn assign_groups(grid: &mut Grid<Boid>, group_count: u8) {
+    for (i, node) in grid.values.iter_mut().enumerate() {
+        node.val.group = (i % group_count as usize) as u8;
+    }
+}
+
+/// Returns the amount of predators in the `grid`.
+pub fn count_predators(grid: &Grid<Boid>) -> usize {
+    grid.iter_all()
+        .filter(|boid| boid.kind == BoidKind::Predator)
+        .count()
+}
+
+/// Turns prey in the `grid` into predators, or predators into prey, until the
+/// grid holds `predator_count` predators, or as many as it can. The kind of
+/// every other boid is kept, independent of the order of the boids.
+fn assign_predators(grid: &mut Grid<Boid>, predator_count: usize) {
+    let current = count_predators(grid);
+    let (from, to, mut changes) = if current < predator_count {
+        (BoidKind::Prey, BoidKind::Predator, predator_count - current)
+    } else {
+        (BoidKind::Predator, BoidKind::Prey, current - predator_count)
+    };
+    for node in grid.values.iter_mut() {
+        if changes == 0 {
+            break;
+        }
+        if node.val.kind == from {
+            node.val.kind = to;
+            changes -= 1;
+        }
+    }
+}
+
 /// Resizes the grid by creating a new one according to the current
 /// `boid_settings` and moving all boids to their correct positions within the new
 /// grid.
@@ -143,11 +410,355 @@ fn resize_grid(grid: &mut Grid<Boid>, boid_settings: &BoidSettings) {
 }
 
 /// Update the location of every boid in the grid based on the given
-/// `boid_settings` across a given `delta` time frame.
-pub fn update_boids(grid: &mut Grid<Boid>, boid_settings: &BoidSettings, delta: f32) {
-    let boid_count = grid.values.len();
+/// `boid_settings` and `environment` across a given `delta` time frame, see
+/// [`move_boids`], after which the lifecycle of the boids is updated when the
+/// ecosystem is enabled.
+pub fn update_boids(
+    grid: &mut Grid<Boid>,
+    boid_settings: &BoidSettings,
+    environment: &Environment,
+    delta: f32,
+) {
+    move_boids(grid, boid_settings, environment, delta);
+    if boid_settings.ecosystem {
+        update_lifecycle(grid, boid_settings, environment, delta);
+    }
+}
+
+/// Moves every boid in the grid in the update mode of the `boid_settings`
+/// across a given `delta` time frame, without adding or removing boids.
+/// The grid is rebuilt first if its wrapping no longer matches the borders.
+fn move_boids(
+    grid: &mut Grid<Boid>,
+    boid_settings: &BoidSettings,
+    environment: &Environment,
+    delta: f32,
+) {
+    let border_settings = boid_settings.border_settings;
+    if grid.wrap_rows != border_settings.wraps_vertically()
+        || grid.wrap_columns != border_settings.wraps_horizontally()
+    {
+        resize_grid(grid, boid_settings);
+    }
+    match boid_settings.update_mode {
+        UpdateMode::Sequential => {
+            let boid_count = grid.values.len();
 
-    for i in 0..boid_count {
-        simulation::update_boid(i, grid, boid_settings, delta);
+            for i in 0..boid_count {
+                simulation::update_boid(i, grid, boid_settings, environment, delta);
+            }
+        }
+        UpdateMode::Synchronous => {
+            simulation::update_boids_synchronous(grid, boid_settings, environment, delta)
+        }
+    }
+}
+
+/// Ages the boids in the `grid` across the `delta` time frame, after which
+/// they graze, feed at the food sources and attractors in the `environment`, reproduce when
+/// their energy reaches the reproduction energy and die when their energy runs
+/// out or they exceed the maximum age in the `boid_settings`.
+///
+/// Offspring are born on the screen near their parent, take half of the
+/// energy of their parent and join the kind and group of their parent, as long
+/// as the population stays below
+/// [`MAX_POPULATION`]. As predators may die or be born, the predator count
+/// must be synchronised afterwards, see [`BoidSettings::sync_predator_count`].
+fn update_lifecycle(
+    grid: &mut Grid<Boid>,
+    boid_settings: &BoidSettings,
+    environment: &Environment,
+    delta: f32,
+) {
+    let mut offspring = Vec::new();
+    // Iterate backwards, as removed boids are replaced by already updated ones.
+    for index in (0..grid.count).rev() {
+        let boid = &mut grid.values[index].val;
+        boid.age += delta;
+        boid.energy += (boid_settings.grazing_rate - boid_settings.energy_decay) * delta;
+        if environment.has_food_at(boid.position) {
+            boid.energy += boid_settings.feeding_rate * delta;
+        }
+
+        let starved = boid.energy <= 0.0;
+        let too_old = boid_settings.max_age > 0.0 && boid.age > boid_settings.max_age;
+        if starved || too_old {
+            remove_boid(grid, boid_settings, index);
+        } else if boid.energy >= boid_settings.reproduction_energy
+            && grid.count + offspring.len() < MAX_POPULATION
+        {
+            boid.energy *= 0.5;
+            let offset = Vector2::new(fastrand::f32() - 0.5, fastrand::f32() - 0.5);
+            let position = boid.position + offset * (BIRTH_RADIUS * 2.0);
+            let mut child = Boid::new(
+                clamp_to_screen(position, boid_settings),
+                boid.velocity,
+                boid.group,
+            );
+            child.energy = boid.energy;
+            child.kind = boid.kind;
+            offspring.push(child);
+        }
+    }
+
+    for child in offspring {
+        let (grid_row, grid_column) = get_grid_position(child.position, boid_settings, grid);
+        grid.add_val(child, grid_row, grid_column);
+    }
+}
+
+/// The largest distance between the positions of the same boid after
+/// updating copies of a grid in different ways, see [`compare_update_modes`].
+pub struct UpdateComparison {
+    /// Between the sequential and synchronous update modes.
+    pub sequential: f32,
+    /// Between the synchronous update of the boids and of the boids stored
+    /// in reverse order.
+    pub reordered: f32,
+}
+
+/// Returns the largest distance between the positions of the same boid in
+/// `grid_a` and `grid_b`, where `index_b` maps the index of a boid in `grid_a`
+/// to its index in `grid_b`.
+fn max_deviation(
+    grid_a: &Grid<Boid>,
+    grid_b: &Grid<Boid>,
+    index_b: impl Fn(usize) -> usize,
+) -> f32 {
+    grid_a
+        .iter_all()
+        .enumerate()
+        .map(|(i, boid)| (grid_b.values[index_b(i)].val.position - boid.position).magnitude())
+        .fold(0.0, f32::max)
+}
+
+/// Compares a single update of copies of the `grid` across the `delta` time
+/// frame, without changing the `grid` itself. The sequential update is
+/// compared to the synchronous update, which in turn is compared to the
+/// synchronous update of the same boids stored in reverse order.
+///
+/// Only the movement of the boids is compared, see [`move_boids`], such that
+/// the boids keep their index. Everything random is disabled during the
+/// comparison, being the noise, the hops of crabs and the landing of boids,
+/// while perched boids stay perched. Any remaining difference in the
+/// reordered update comes from rounding, as the neighbours are summed in
+/// another order.
+pub fn compare_update_modes(
+    grid: &Grid<Boid>,
+    boid_settings: &BoidSettings,
+    environment: &Environment,
+    delta: f32,
+) -> UpdateComparison {
+    let mut boid_settings = boid_settings.clone();
+    boid_settings.set_noise(0.0);
+    boid_settings.hop_rate = 0.0;
+    boid_settings.perch_rate = 0.0;
+    boid_settings.takeoff_count = 0;
+    boid_settings.disturb_range = 0.0;
+
+    let mut start = grid.clone();
+    for node in start.values.iter_mut() {
+        if let FlightState::Perched { remaining } = &mut node.val.flight {
+            *remaining = remaining.max(0.0) + delta;
+        }
+    }
+
+    let mut sequential = start.clone();
+    boid_settings.set_update_mode(UpdateMode::Sequential);
+    move_boids(&mut sequential, &boid_settings, environment, delta);
+
+    let mut synchronous = start.clone();
+    let mut reordered: Grid<Boid> = grid_init(start.count, &boid_settings);
+    for boid in start.values.iter().rev().map(|node| node.val) {
+        let (grid_row, grid_column) = get_grid_position(boid.position, &boid_settings, &reordered);
+        reordered.add_val(boid, grid_row, grid_column);
+    }
+    boid_settings.set_update_mode(UpdateMode::Synchronous);
+    move_boids(&mut synchronous, &boid_settings, environment, delta);
+    move_boids(&mut reordered, &boid_settings, environment, delta);
+
+    let last = grid.count.saturating_sub(1);
+    UpdateComparison {
+        sequential: max_deviation(&sequential, &synchronous, |i| i),
+        reordered: max_deviation(&synchronous, &reordered, |i| last - i),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a grid holding the `boids` in the given order.
+    fn grid_of(boids: &[Boid], boid_settings: &BoidSettings) -> Grid<Boid> {
+        let mut grid = grid_init(boids.len(), boid_settings);
+        for boid in boids {
+            let (grid_row, grid_column) = get_grid_position(boid.position, boid_settings, &grid);
+            grid.add_val(*boid, grid_row, grid_column);
+        }
+        grid
+    }
+
+    #[test]
+    fn synchronous_update_is_independent_of_order() {
+        let (width, height) = (60, 60);
+        let mut boid_settings = BoidSettings::new(2.0, 5.0, 0.01, 0.05, 0.05, width, height);
+        boid_settings
+            .set_border(BorderSettings::Bounded)
+            .set_margin(10.0)
+            .set_turn_force(1.5)
+            .set_min_speed(2.0)
+            .set_noise(0.0)
+            // A budget far below the density, which would otherwise favour
+            // the boids stored first in each cell.
+            .set_neighbour_sampling(NeighbourSampling::Strided, 10)
+            .set_update_mode(UpdateMode::Synchronous);
+
+        let mut rng = fastrand::Rng::with_seed(7);
+        let boids: Vec<Boid> = (0..400)
+            .map(|_| {
+                let position = Vector2::new(rng.f32() * width as f32, rng.f32() * height as f32);
+                let velocity = Vector2::new(rng.f32() - 0.5, rng.f32() - 0.5) * 4.0;
+                Boid::new(position, velocity, 0)
+            })
+            .collect();
+        let mut order: Vec<usize> = (0..boids.len()).collect();
+        rng.shuffle(&mut order);
+        let shuffled: Vec<Boid> = order.iter().map(|&index| boids[index]).collect();
+
+        let environment = Environment::new(width, height);
+        let mut grid = grid_of(&boids, &boid_settings);
+        let mut shuffled_grid = grid_of(&shuffled, &boid_settings);
+        for _ in 0..5 {
+            update_boids(&mut grid, &boid_settings, &environment, 0.2);
+            update_boids(&mut shuffled_grid, &boid_settings, &environment, 0.2);
+        }
+
+        for (shuffled_index, &index) in order.iter().enumerate() {
+            let boid = grid.values[index].val;
+            let shuffled_boid = shuffled_grid.values[shuffled_index].val;
+            assert!((boid.position - shuffled_boid.position).magnitude() < 1e-3);
+            assert!((boid.velocity - shuffled_boid.velocity).magnitude() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn comparing_update_modes_ignores_randomness() {
+        let (width, height) = (60, 60);
+        let mut boid_settings = BoidSettings::new(2.0, 5.0, 0.01, 0.05, 0.05, width, height);
+        boid_settings
+            .set_border(BorderSettings::Bounded)
+            .set_noise(5.0)
+            .set_noise_model(NoiseModel::Levy)
+            .set_ecosystem(true, 0.0, 0.0, 1.0, 0.1, 1000.0)
+            .set_perching(true, 100.0, 10.0, 1, 10.0);
+
+        let mut rng = fastrand::Rng::with_seed(3);
+        let boids: Vec<Boid> = (0..200)
+            .map(|_| {
+                let position = Vector2::new(rng.f32() * width as f32, rng.f32() * height as f32);
+                let velocity = Vector2::new(rng.f32() - 0.5, rng.f32() - 0.5) * 4.0;
+                Boid::new(position, velocity, 0)
+            })
+            .collect();
+        let grid = grid_of(&boids, &boid_settings);
+        let environment = Environment::new(width, height);
+        let comparison = compare_update_modes(&grid, &boid_settings, &environment, 0.2);
+        assert!(comparison.reordered < 1e-3);
+        assert_eq!(grid.count, boids.len());
+    }
+
+    #[test]
+    fn removing_boids_keeps_predator_count() {
+        let mut boid_settings = BoidSettings::new(2.0, 5.0, 0.01, 0.05, 0.05, 40, 40);
+        let mut grid = grid_init(0, &boid_settings);
+        set_boid_count(&mut grid, &mut boid_settings, 20);
+        boid_settings.set_predator_count(3, &mut grid);
+
+        // Removing the first boid moves the last boid, a prey, to its place.
+        remove_boid(&mut grid, &boid_settings, 0);
+        boid_settings.sync_predator_count(&grid);
+        assert_eq!(boid_settings.predator_count, 2);
+        assert_eq!(count_predators(&grid), 2);
+
+        boid_settings.set_predator_count(4, &mut grid);
+        assert_eq!(count_predators(&grid), 4);
+        set_boid_count(&mut grid, &mut boid_settings, 2);
+        assert_eq!(boid_settings.predator_count, count_predators(&grid));
+
+        boid_settings.set_predator_count(5, &mut grid);
+        assert_eq!(boid_settings.predator_count, 2);
+    }
+
+    #[test]
+    fn spawning_stays_on_screen() {
+        let boid_settings = BoidSettings::new(2.0, 5.0, 0.01, 0.05, 0.05, 40, 40);
+        let mut grid = grid_init(0, &boid_settings);
+        assert!(spawn_boid(
+            &mut grid,
+            &boid_settings,
+            Vector2::new(100.0, 100.0),
+            Vector2::ZERO,
+        ));
+        let position = grid.values[0].val.position;
+        assert!(position.x < 40.0 && position.y < 40.0);
+        assert_eq!(grid.iter_from_index(grid.grid.len() as i32 - 1).count(), 1);
+    }
+
+    #[test]
+    fn offspring_stay_on_screen() {
+        let (width, height) = (20, 20);
+        let mut boid_settings = BoidSettings::new(2.0, 5.0, 0.01, 0.05, 0.05, width, height);
+        boid_settings.set_ecosystem(true, 0.0, 0.0, 0.0, 0.5, 0.0);
+        let corners = [
+            Vector2::ZERO,
+            Vector2::new(19.9, 0.0),
+            Vector2::new(0.0, 19.9),
+            Vector2::new(19.9, 19.9),
+        ];
+        let boids: Vec<Boid> = corners
+            .iter()
+            .flat_map(|&corner| [Boid::new(corner, Vector2::ZERO, 0); 25])
+            .collect();
+        let mut grid = grid_of(&boids, &boid_settings);
+        update_lifecycle(
+            &mut grid,
+            &boid_settings,
+            &Environment::new(width, height),
+            0.1,
+        );
+
+        assert_eq!(grid.count, boids.len() * 2);
+        // Every boid is linked into a cell.
+        let linked: usize = grid.grid.iter().map(|cell| cell.count as usize).sum();
+        assert_eq!(linked, grid.count);
+        for boid in grid.iter_all() {
+            assert!((0.0..width as f32).contains(&boid.position.x));
+            assert!((0.0..height as f32).contains(&boid.position.y));
+        }
+    }
+
+    #[test]
+    fn default_ecosystem_survives_without_food() {
+        let mut boid_settings = BoidSettings::new(2.0, 5.0, 0.01, 0.05, 0.05, 40, 40);
+        boid_settings.set_ecosystem(
+            true,
+            crate::ENERGY_DECAY,
+            crate::FEEDING_RATE,
+            crate::GRAZING_RATE,
+            crate::REPRODUCTION_ENERGY,
+            crate::MAX_AGE,
+        );
+        let mut grid = grid_init(0, &boid_settings);
+        set_boid_count(&mut grid, &mut boid_settings, 50);
+        boid_settings.set_predator_count(5, &mut grid);
+        let environment = Environment::new(40, 40);
+
+        for _ in 0..10000 {
+            update_lifecycle(&mut grid, &boid_settings, &environment, 1.0);
+            assert!(grid.count > 0);
+        }
+        // Predators follow the same rules, so the initial ones died of age.
+        assert!(count_predators(&grid) > 0);
+        assert!(grid.iter_all().all(|boid| boid.age <= crate::MAX_AGE));
     }
 }
@@ -0,0 +1,122 @@
+//! Attraction and repulsion between groups of boids.
+//!
+//! # Affinity
+//!
+//! Contains the [`AffinityMatrix`], an N×N matrix of coefficients where the
+//! entry at row `i` and column `j` scales how strongly boids of group `i` are
+//! attracted to, or repelled by when negative, boids of group `j`.
+//!
+//! ## File format
+//! Matrices are stored as plain text, with each row of the matrix on its own
+//! line and the coefficients in the row separated by whitespace. Lines
+//! starting with `#` and empty lines are ignored.
+//!
+//! ```text
+//! # Group 0 chases group 1, which avoids group 0.
+//!  1.0  0.5
+//! -0.5  1.0
+//! ```
+
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+};
+
+/// The most groups the boids can be divided in.
+pub const MAX_GROUPS: usize = 8;
+
+/// Square matrix of the affinity between every pair of groups.
+#[derive(Debug, Clone)]
+pub struct AffinityMatrix {
+    /// The amount of rows and columns of the matrix.
+    size: usize,
+    /// The coefficients in row major order.
+    values: Vec<f32>,
+}
+
+impl AffinityMatrix {
+    /// Creates a new [`AffinityMatrix`] for `size` groups, where groups are
+    /// only attracted to their own group.
+    pub fn identity(size: usize) -> AffinityMatrix {
+        let mut values = vec![0.0; size * size];
+        for i in 0..size {
+            values[i + i * size] = 1.0;
+        }
+        AffinityMatrix { size, values }
+    }
+
+    /// Returns the amount of groups covered by the matrix.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the affinity of `group` towards the `other` group.
+    #[inline]
+    pub fn get(&self, group: u8, other: u8) -> f32 {
+        self.values[other as usize + group as usize * self.size]
+    }
+
+    /// Sets the affinity of `group` towards the `other` group.
+    #[inline]
+    pub fn set(&mut self, group: u8, other: u8, affinity: f32) {
+        self.values[other as usize + group as usize * self.size] = affinity;
+    }
+
+    /// Resizes the matrix to cover `size` groups, keeping the affinities
+    /// between the remaining groups. New groups are only attracted to their
+    /// own group.
+    pub fn resize(&mut self, size: usize) {
+        let mut resized = AffinityMatrix::identity(size);
+        for row in 0..self.size.min(size) {
+            for column in 0..self.size.min(size) {
+                resized.values[column + row * size] = self.values[column + row * self.size];
+            }
+        }
+        *self = resized;
+    }
+
+    /// Reads a matrix from the file at `file_path`, see the
+    /// [module documentation](self) for the file format.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file can not be read, or does
+    /// not contain a square matrix of numbers covering at most [`MAX_GROUPS`]
+    /// groups.
+    pub fn load(file_path: &str) -> Result<AffinityMatrix> {
+        let contents = fs::read_to_string(file_path)?;
+        let invalid = |message: &str| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("{}: {}", file_path, message),
+            )
+        };
+
+        let mut rows: Vec<Vec<f32>> = Vec::new();
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let row = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<std::result::Result<_, _>>()
+                .map_err(|_| invalid("expected only numbers"))?;
+            rows.push(row);
+        }
+
+        let size = rows.len();
+        if size == 0 || rows.iter().any(|row| row.len() != size) {
+            return Err(invalid("expected a square matrix"));
+        }
+        if size > MAX_GROUPS {
+            return Err(invalid(&format!("expected at most {} groups", MAX_GROUPS)));
+        }
+        Ok(AffinityMatrix {
+            size,
+            values: rows.concat(),
+        })
+    }
+}
@@ -0,0 +1,100 @@
+//! Non-rectangular arenas containing the boids.
+//!
+//! # Arena
+//!
+//! Contains the [`ArenaShape`] of the border used by
+//! [`BorderSettings::Arena`](super::BorderSettings::Arena), fitted to the
+//! screen. Every shape measures the signed distance of a position to its
+//! border, along with the outward normal of the border, such that the boids
+//! can either be turned around softly within the margin of the border or be
+//! reflected off it.
+
+use crate::vector2::Vector2;
+
+/// The part of the shorter side of the screen used as the radius of the
+/// corners of the rounded rectangle.
+const CORNER_RADIUS: f32 = 0.25;
+/// How far within the border positions are moved when contained.
+const CONTAIN_MARGIN: f32 = 0.01;
+
+/// The shape of an arena, fitted to a screen of a given width and height.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArenaShape {
+    /// A circle centered on the screen, touching its shorter sides
+    Circle,
+    /// An ellipse touching all sides of the screen
+    Ellipse,
+    /// The full screen with rounded corners
+    RoundedRectangle,
+}
+
+impl ArenaShape {
+    /// Returns the signed distance from the `position` to the border of the
+    /// arena on a screen of the given `width` and `height`, which is negative
+    /// inside of the arena, along with the outward normal of the border
+    /// closest to the `position`. The distance to the ellipse is estimated
+    /// from its gradient.
+    pub fn distance(&self, position: Vector2, width: usize, height: usize) -> (f32, Vector2) {
+        let half_size = Vector2::new(width as f32, height as f32) / 2.0;
+        let offset = position - half_size;
+        match self {
+            ArenaShape::Circle => {
+                let radius = half_size.x.min(half_size.y);
+                let distance = offset.magnitude();
+                (distance - radius, offset.normalised())
+            }
+            ArenaShape::Ellipse => {
+                let scaled = Vector2::new(offset.x / half_size.x, offset.y / half_size.y);
+                let level = scaled.magnitude();
+                if level == 0.0 {
+                    return (-half_size.x.min(half_size.y), Vector2::ZERO);
+                }
+                let gradient = Vector2::new(
+                    scaled.x / (half_size.x * level),
+                    scaled.y / (half_size.y * level),
+                );
+                ((level - 1.0) / gradient.magnitude(), gradient.normalised())
+            }
+            ArenaShape::RoundedRectangle => {
+                let radius = half_size.x.min(half_size.y) * CORNER_RADIUS;
+                let sign = Vector2::new(offset.x.signum(), offset.y.signum());
+                let corner = Vector2::new(
+                    offset.x.abs() - (half_size.x - radius),
+                    offset.y.abs() - (half_size.y - radius),
+                );
+                if corner.x > 0.0 && corner.y > 0.0 {
+                    (corner.magnitude() - radius, (corner * sign).normalised())
+                } else if corner.x > corner.y {
+                    (corner.x - radius, Vector2::new(sign.x, 0.0))
+                } else {
+                    (corner.y - radius, Vector2::new(0.0, sign.y))
+                }
+            }
+        }
+    }
+
+    /// Moves the `position` back inside of the arena on a screen of the given
+    /// `width` and `height` if it lies outside of it. The circle and ellipse
+    /// scale the position towards the center, while the rounded rectangle
+    /// moves it along the normal of its border.
+    pub fn contain(&self, position: Vector2, width: usize, height: usize) -> Vector2 {
+        let (distance, normal) = self.distance(position, width, height);
+        if distance <= 0.0 {
+            return position;
+        }
+        let half_size = Vector2::new(width as f32, height as f32) / 2.0;
+        let offset = position - half_size;
+        match self {
+            ArenaShape::Circle => {
+                let radius = half_size.x.min(half_size.y);
+                half_size + offset * ((1.0 - CONTAIN_MARGIN) * radius / offset.magnitude())
+            }
+            ArenaShape::Ellipse => {
+                let level =
+                    Vector2::new(offset.x / half_size.x, offset.y / half_size.y).magnitude();
+                half_size + offset * ((1.0 - CONTAIN_MARGIN) / level)
+            }
+            ArenaShape::RoundedRectangle => position - normal * (distance + CONTAIN_MARGIN),
+        }
+    }
+}
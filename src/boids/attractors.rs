@@ -0,0 +1,76 @@
+//! Points in the world that draw boids in and feed them.
+//!
+//! # Attractors
+//!
+//! Contains the definition of an [`Attractor`], a persistent point pulling in
+//! the boids within its radius, similar to the force of the mouse. Boids only
+//! seek the nearest active attractor in range, see
+//! [`Environment::nearest_attractor`](super::Environment::nearest_attractor).
+//!
+//! ## Food
+//! While the ecosystem is enabled in the [`BoidSettings`](super::BoidSettings),
+//! boids within the radius of an active attractor feed from it, just like at a
+//! [`FoodSource`](super::FoodSource) that does not pull them in. Attractors
+//! with a depletion rate run out while boids are within their radius, after
+//! which they become inactive.
+
+use crate::vector2::Vector2;
+
+/// A point pulling in, or pushing away when the strength is negative, the
+/// boids within its radius.
+#[derive(Debug, Clone, Copy)]
+pub struct Attractor {
+    /// The center of the attractor.
+    pub position: Vector2,
+    /// How strongly boids are pulled towards the center.
+    pub strength: f32,
+    /// From how far the attractor has an effect.
+    pub radius: f32,
+    /// How much of the attractor is used up per boid per unit of time, or
+    /// zero if the attractor never runs out.
+    pub depletion: f32,
+    /// The remaining part of the attractor, where the attractor is active
+    /// as long as some remains.
+    pub amount: f32,
+}
+
+impl Attractor {
+    /// Creates a new full [`Attractor`] at `position` with the given
+    /// `strength`, `radius` and `depletion` rate.
+    pub fn new(position: Vector2, strength: f32, radius: f32, depletion: f32) -> Attractor {
+        Attractor {
+            position,
+            strength,
+            radius,
+            depletion,
+            amount: 1.0,
+        }
+    }
+
+    /// Returns whether the attractor has not run out yet.
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.amount > 0.0
+    }
+
+    /// Returns whether a boid at `position` is within the radius of the
+    /// attractor.
+    #[inline]
+    pub fn reaches(&self, position: Vector2) -> bool {
+        (position - self.position).sqr_magnitude() < self.radius * self.radius
+    }
+
+    /// Returns the force exerted on a boid at `position` within the radius,
+    /// which scales with the normalised distance to the center like the
+    /// attracting force of the mouse.
+    #[inline]
+    pub fn force(&self, position: Vector2) -> Vector2 {
+        (self.position - position) * (self.strength / self.radius)
+    }
+
+    /// Uses up the attractor by the `boid_count` boids within its radius
+    /// across the `delta` time frame.
+    pub fn deplete(&mut self, boid_count: usize, delta: f32) {
+        self.amount = (self.amount - self.depletion * boid_count as f32 * delta).max(0.0);
+    }
+}
@@ -0,0 +1,88 @@
+//! A clock showing the current time with the boids.
+//!
+//! # Clock
+//!
+//! Contains the [`Clock`], which keeps the [`Formation`] of the
+//! [`Environment`] spelling out the current time as the outlines of large
+//! digits, drawn using the font of [`Bitmap::from_text`]. Whenever the minute
+//! changes, the formation is replaced by one of the new time, after which its
+//! targets are assigned to the nearby boids, letting the flock flow from the
+//! old digits to the new ones.
+//!
+//! ## Time zone
+//! Unless an offset from UTC is given, the clock shows the local time of the
+//! system, following its changes to and from daylight saving time.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::Local;
+
+use super::{
+    environment::Environment,
+    formation::{Bitmap, Formation},
+};
+
+/// The amount of seconds in a day.
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Shows the current time as the formation of the boids.
+pub struct Clock {
+    /// The offset from UTC of the shown time in seconds, or `None` to show the
+    /// local time.
+    utc_offset: Option<i64>,
+    /// The time currently shown, if any.
+    shown: Option<String>,
+    /// The formation replaced by the clock, restored once the clock stops.
+    replaced: Option<Formation>,
+}
+
+impl Clock {
+    /// Creates a new [`Clock`] showing the time at the given offset from UTC
+    /// in `utc_offset_hours`, or the local time if `None`, replacing the
+    /// `replaced` formation.
+    pub fn new(utc_offset_hours: Option<f32>, replaced: Option<Formation>) -> Clock {
+        Clock {
+            utc_offset: utc_offset_hours.map(|hours| (hours * 3600.0) as i64),
+            shown: None,
+            replaced,
+        }
+    }
+
+    /// Returns the current time as hours and minutes, such as `09:41`.
+    pub fn time(&self) -> String {
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs() as i64);
+        // The local offset is read every time, as it changes with daylight
+        // saving time.
+        let utc_offset = self
+            .utc_offset
+            .unwrap_or_else(|| Local::now().offset().local_minus_utc() as i64);
+        let seconds = (since_epoch + utc_offset).rem_euclid(SECONDS_PER_DAY);
+        format!("{:02}:{:02}", seconds / 3600, seconds / 60 % 60)
+    }
+
+    /// Replaces the formation of the `environment` by the outlines of the
+    /// current time once it differs from the time shown, fitting it to a
+    /// screen of the given `width` and `height`. Whether the formation is
+    /// released is kept.
+    pub fn update(&mut self, environment: &mut Environment, width: usize, height: usize) {
+        let time = self.time();
+        if self.shown.as_ref() == Some(&time) && environment.formation.is_some() {
+            return;
+        }
+        let released = environment
+            .formation
+            .as_ref()
+            .is_some_and(|formation| formation.released);
+        let mut formation = Formation::outline(Bitmap::from_text(&time), width, height);
+        formation.released = released;
+        environment.set_formation(Some(formation));
+        self.shown = Some(time);
+    }
+
+    /// Stops the clock, returning the formation it replaced.
+    pub fn stop(self) -> Option<Formation> {
+        self.replaced
+    }
+}
@@ -0,0 +1,170 @@
+//! Persistent features of the world the boids fly through.
+//!
+//! # Environment
+//!
+//! Unlike the [`BoidSettings`](super::BoidSettings), which describe how boids
+//! behave, the [`Environment`] describes what the world contains. Its contents
+//! are kept when the settings are reset.
+
+use super::{
+    Boid, attractors::Attractor, flow::FlowField, food::FoodSource, formation::Formation,
+    obstacles::Obstacle, paths::Path, walls::WallLayer,
+};
+use crate::{grid::Grid, vector2::Vector2};
+
+/// Contains everything placed in the world that the boids interact with.
+pub struct Environment {
+    /// The static obstacles boids steer around.
+    pub obstacles: Vec<Obstacle>,
+    /// The walls painted into the world.
+    pub walls: WallLayer,
+    /// The paths followed by the boids, where the path at index `i` is
+    /// followed by the group `i`.
+    pub paths: Vec<Path>,
+    /// The food sources at which boids regain energy.
+    pub food: Vec<FoodSource>,
+    /// The attractors pulling in and feeding the boids.
+    pub attractors: Vec<Attractor>,
+    /// The formation the boids take on, if any.
+    pub formation: Option<Formation>,
+    /// The forces flowing across the world.
+    pub flow: FlowField,
+}
+
+impl Environment {
+    /// Creates a new empty [`Environment`] for a world of the given `width`
+    /// and `height`.
+    pub fn new(width: usize, height: usize) -> Environment {
+        Environment {
+            obstacles: Vec::new(),
+            walls: WallLayer::new(width, height),
+            paths: Vec::new(),
+            food: Vec::new(),
+            attractors: Vec::new(),
+            formation: None,
+            flow: FlowField::new(width, height),
+        }
+    }
+
+    /// Sets the `paths` followed by the groups of boids.
+    pub fn set_paths(&mut self, paths: Vec<Path>) -> &mut Self {
+        self.paths = paths;
+        self
+    }
+
+    /// Returns the path followed by the given `group`, if any.
+    #[inline]
+    pub fn path_for_group(&self, group: u8) -> Option<&Path> {
+        self.paths.get(group as usize)
+    }
+
+    /// Sets the `formation` the boids take on.
+    pub fn set_formation(&mut self, formation: Option<Formation>) -> &mut Self {
+        self.formation = formation;
+        self
+    }
+
+    /// Returns the target of the boid at `index` out of `count` boids in the
+    /// formation, if any, see [`Formation::target`].
+    #[inline]
+    pub fn formation_target(&self, index: usize, count: usize) -> Option<Vector2> {
+        self.formation
+            .as_ref()
+            .and_then(|formation| formation.target(index, count))
+    }
+
+    /// Assigns the targets of the formation, if any, to the boids in the
+    /// `grid` once the amount of boids no longer matches the assignment, see
+    /// [`Formation::assign`].
+    pub fn assign_formation(&mut self, grid: &Grid<Boid>) {
+        if let Some(formation) = &mut self.formation
+            && !formation.released
+            && !formation.is_assigned(grid.count)
+        {
+            let positions: Vec<Vector2> =
+                grid.values.iter().map(|node| node.val.position).collect();
+            formation.assign(&positions);
+        }
+    }
+
+    /// Update the size of the world covered by the environment.
+    pub fn update_window(&mut self, width: usize, height: usize) -> &mut Self {
+        self.walls.resize(width, height);
+        self.flow.resize(width, height);
+        if let Some(formation) = &mut self.formation {
+            formation.fit(width, height);
+        }
+        self
+    }
+
+    /// Adds an `obstacle` to the environment.
+    pub fn add_obstacle(&mut self, obstacle: Obstacle) -> &mut Self {
+        self.obstacles.push(obstacle);
+        self
+    }
+
+    /// Adds a food source to the environment.
+    pub fn add_food(&mut self, food: FoodSource) -> &mut Self {
+        self.food.push(food);
+        self
+    }
+
+    /// Adds an `attractor` to the environment.
+    pub fn add_attractor(&mut self, attractor: Attractor) -> &mut Self {
+        self.attractors.push(attractor);
+        self
+    }
+
+    /// Returns the nearest active attractor which has a boid at `position`
+    /// within its radius, if any.
+    pub fn nearest_attractor(&self, position: Vector2) -> Option<&Attractor> {
+        self.attractors
+            .iter()
+            .filter(|attractor| attractor.is_active() && attractor.reaches(position))
+            .min_by(|a, b| {
+                let distance_a = (a.position - position).sqr_magnitude();
+                let distance_b = (b.position - position).sqr_magnitude();
+                distance_a.total_cmp(&distance_b)
+            })
+    }
+
+    /// Returns whether a boid at `position` is able to feed from any food
+    /// source or active attractor.
+    pub fn has_food_at(&self, position: Vector2) -> bool {
+        self.food.iter().any(|food| food.reaches(position))
+            || self.nearest_attractor(position).is_some()
+    }
+
+    /// Uses up the attractors with a depletion rate by the `boids` within
+    /// their radius across the `delta` time frame.
+    pub fn deplete_attractors<'a>(&mut self, boids: impl Iterator<Item = &'a Boid>, delta: f32) {
+        if self
+            .attractors
+            .iter()
+            .all(|attractor| attractor.depletion == 0.0)
+        {
+            return;
+        }
+        let mut counts = vec![0; self.attractors.len()];
+        for boid in boids {
+            for (count, attractor) in counts.iter_mut().zip(self.attractors.iter()) {
+                if attractor.is_active() && attractor.reaches(boid.position) {
+                    *count += 1;
+                }
+            }
+        }
+        for (count, attractor) in counts.into_iter().zip(self.attractors.iter_mut()) {
+            attractor.deplete(count, delta);
+        }
+    }
+
+    /// Removes all obstacles, walls, food sources and attractors from the
+    /// environment.
+    pub fn clear(&mut self) -> &mut Self {
+        self.obstacles.clear();
+        self.walls.clear();
+        self.food.clear();
+        self.attractors.clear();
+        self
+    }
+}
@@ -0,0 +1,373 @@
+//! Simulation of boids flying through a 3D volume.
+//!
+//! # Flock 3D
+//! Contains the [`Flock3D`], the 3D counterpart of the regular simulation, in
+//! which the boids fly through a volume as deep as the screen is high. The
+//! flock follows the basic rules, along with the gravity, noise, friction and
+//! border forces shared by all groups in the [`BoidSettings`]. Groups,
+//! predators and the environment are not part of the 3D simulation, while the
+//! volume is always bounded.
+//!
+//! ## Grid
+//! The boids are stored in a 3D grid of cells as large as the largest range
+//! in the [`BoidSettings`], such that every neighbour of a boid falls within
+//! the block of 3x3x3 cells around it. The layers of cells along the depth are
+//! stacked on top of each other in the rows of a [`Grid`].
+
+use super::{
+    settings::{BoidSettings, NeighbourSampling},
+    simulation::Reservoir,
+};
+use crate::{grid::Grid, vector3::Vector3};
+
+// Max speed of boids
+const MAX_SPEED: f32 = 100.0;
+/// The amount of cells in the block of 3x3x3 cells around a boid.
+const NEIGHBOUR_CELLS: usize = 27;
+
+/// Simple representation of a boid in three dimensions.
+#[derive(Debug, Copy, Clone)]
+pub struct Boid3D {
+    pub position: Vector3,
+    pub velocity: Vector3,
+    /// The position before the last update, used to interpolate between
+    /// updates when rendering
+    pub previous_position: Vector3,
+}
+
+/// A flock of boids within a bounded volume.
+pub struct Flock3D {
+    /// The boids, stored in the cells of the volume.
+    pub boids: Grid<Boid3D>,
+    /// The width, height and depth of the volume.
+    pub size: Vector3,
+    /// The amount of layers of cells along the depth of the volume.
+    layers: usize,
+    /// The amount of rows of cells within a single layer.
+    rows: usize,
+    /// The width, height and depth of a single cell.
+    cell_size: f32,
+}
+
+impl Flock3D {
+    /// Creates a new [`Flock3D`] of `count` randomly placed boids within a
+    /// volume as wide and high as the screen given in the `boid_settings`.
+    pub fn new(count: usize, boid_settings: &BoidSettings) -> Flock3D {
+        let mut flock = Flock3D {
+            boids: Grid::new(0, 1, 1),
+            size: volume_size(boid_settings.width, boid_settings.height),
+            layers: 1,
+            rows: 1,
+            cell_size: boid_settings.max_range(),
+        };
+        flock.resize_grid(count);
+
+        let min_speed = boid_settings.min_speed;
+        for _ in 0..count {
+            let position =
+                Vector3::new(fastrand::f32(), fastrand::f32(), fastrand::f32()) * flock.size;
+            let direction = Vector3::new(
+                fastrand::f32() - 0.5,
+                fastrand::f32() - 0.5,
+                fastrand::f32() - 0.5,
+            );
+            let boid = Boid3D {
+                position,
+                velocity: direction.normalised() * min_speed,
+                previous_position: position,
+            };
+            let (row, column) = flock.grid_cell(position);
+            flock.boids.add_val(boid, row, column);
+        }
+        flock
+    }
+
+    /// Resizes the volume to the new `width` and `height` of the screen,
+    /// scaling the positions of the boids along with it. Along the axes
+    /// without size before, such as after the screen was resized to nothing,
+    /// the boids are spread out randomly instead.
+    pub fn update_window(&mut self, width: usize, height: usize) {
+        let size = volume_size(width, height);
+        let rescale = |position: f32, old_size: f32, new_size: f32| {
+            if old_size > 0.0 {
+                position * (new_size / old_size)
+            } else {
+                fastrand::f32() * new_size
+            }
+        };
+        for node in self.boids.values.iter_mut() {
+            let boid = &mut node.val;
+            boid.position = Vector3::new(
+                rescale(boid.position.x, self.size.x, size.x),
+                rescale(boid.position.y, self.size.y, size.y),
+                rescale(boid.position.z, self.size.z, size.z),
+            );
+            boid.previous_position = boid.position;
+        }
+        self.size = size;
+        self.resize_grid(self.boids.count);
+    }
+
+    /// Updates the location of every boid in the flock based on the given
+    /// `boid_settings` across a given `delta` time frame.
+    pub fn update(&mut self, boid_settings: &BoidSettings, delta: f32) {
+        // Keep the cells as large as the largest range.
+        let range = boid_settings.max_range();
+        if range != self.cell_size {
+            self.cell_size = range;
+            self.resize_grid(self.boids.count);
+        }
+
+        for index in 0..self.boids.count {
+            let boid = self.boids.values[index].val;
+            let accel = self.rules(index, &boid, boid_settings)
+                + self.border_force(boid.position, boid_settings)
+                + rand_diffuse(boid_settings, delta)
+                - drag(boid.velocity, boid_settings)
+                + Vector3::new(0.0, boid_settings.gravity, 0.0);
+
+            let velocity = clip_speed(boid.velocity + accel * delta, boid_settings);
+            let mut position = boid.position + velocity * delta;
+            position.x = position.x.clamp(0.0, self.size.x);
+            position.y = position.y.clamp(0.0, self.size.y);
+            position.z = position.z.clamp(0.0, self.size.z);
+
+            // Move the boid to its new cell.
+            let (row, column) = self.grid_cell(boid.position);
+            let (new_row, new_column) = self.grid_cell(position);
+            if (row, column) != (new_row, new_column) {
+                let prev_index = self.boids.find_prev_index(index, row, column);
+                self.boids.unlink_val(index, prev_index, row, column);
+                self.boids.link_val(index, new_row, new_column);
+            }
+
+            let boid = &mut self.boids.values[index].val;
+            boid.previous_position = boid.position;
+            boid.position = position;
+            boid.velocity = velocity;
+        }
+    }
+
+    /// Returns the force resulting from the separation, cohesion and alignment
+    /// with the neighbours of the `boid` at `index` within the surrounding
+    /// cells. Like in 2D, the neighbours are sampled as given by the
+    /// [`BoidSettings::effective_sampling`], limited to the sample budget.
+    fn rules(&self, index: usize, boid: &Boid3D, boid_settings: &BoidSettings) -> Vector3 {
+        let group_settings = boid_settings.shared_group();
+        let (layer, row, column) = self.cell_of(boid.position);
+
+        // The first boid in each surrounding cell along with the cumulative
+        // boid count up to and including the cell.
+        let mut cells = [(Grid::<Boid3D>::EMPTY, 0.0); NEIGHBOUR_CELLS];
+        let mut total = 0.0;
+        let mut i = 0;
+        for l in (layer - 1)..=(layer + 1) {
+            for r in (row - 1)..=(row + 1) {
+                for c in (column - 1)..=(column + 1) {
+                    let cell_index = self.boids.index_from_pos(self.grid_row(l, r), c);
+                    if cell_index != Grid::<Boid3D>::EMPTY {
+                        total += self.boids.grid[cell_index as usize].count as f32;
+                    }
+                    cells[i] = (cell_index, total);
+                    i += 1;
+                }
+            }
+        }
+
+        let mut avg = Vector3::ZERO;
+        let mut align = Vector3::ZERO;
+        let mut vis_count = 0.0;
+        let mut sep = Vector3::ZERO;
+        let mut prot_count = 0.0;
+        let mut add = |other_index: usize| {
+            let other = &self.boids.values[other_index].val;
+            let diff = other.position - boid.position;
+            let distance = diff.sqr_magnitude();
+            if distance < group_settings.sqr_protected_range {
+                sep -= diff;
+                prot_count += 1.0;
+            } else if distance < group_settings.sqr_visible_range {
+                avg += diff;
+                align += other.velocity;
+                vis_count += 1.0;
+            }
+        };
+
+        // Every boid in the surrounding cells besides the boid itself.
+        let candidates = (total as usize).saturating_sub(1);
+        let budget = boid_settings.sample_budget;
+        let others = cells.iter().flat_map(|&(cell_index, _)| {
+            self.boids
+                .iter_from_index(cell_index)
+                .filter(|&other_index| other_index != index)
+        });
+        match boid_settings.effective_sampling() {
+            NeighbourSampling::Strided => {
+                // Stride through the cells proportionally to their density.
+                let increment = (total / budget as f32).max(1.0);
+                let mut acc = 0.0;
+                for (cell_index, bin) in cells {
+                    for other_index in self.boids.iter_from_index(cell_index) {
+                        if acc >= bin {
+                            break;
+                        }
+                        acc += increment;
+                        if other_index != index {
+                            add(other_index);
+                        }
+                    }
+                }
+            }
+            NeighbourSampling::Reservoir if candidates > budget => {
+                let mut reservoir = Reservoir::new(budget);
+                others.for_each(|other_index| reservoir.offer(other_index));
+                reservoir.samples().into_iter().for_each(add);
+            }
+            _ => others.for_each(add),
+        }
+
+        if prot_count > 0.0 {
+            sep /= prot_count;
+        }
+        if vis_count > 0.0 {
+            avg /= vis_count;
+            align /= vis_count;
+        }
+        avg * group_settings.cohesion
+            + align * group_settings.alignment
+            + sep * group_settings.separation
+    }
+
+    /// Gives the force pushing boids within the margin of the `boid_settings`
+    /// back towards the center of the volume.
+    fn border_force(&self, position: Vector3, boid_settings: &BoidSettings) -> Vector3 {
+        let turn_force = boid_settings.turn_force;
+        let margin = boid_settings.margin;
+        let axis_force = |position: f32, size: f32| {
+            if position < margin {
+                turn_force
+            } else if position > size - margin {
+                -turn_force
+            } else {
+                0.0
+            }
+        };
+        Vector3::new(
+            axis_force(position.x, self.size.x),
+            axis_force(position.y, self.size.y),
+            axis_force(position.z, self.size.z),
+        )
+    }
+
+    /// Returns the layer, row and column of the cell containing the `position`.
+    fn cell_of(&self, position: Vector3) -> (i32, i32, i32) {
+        let cell = |position: f32, count: usize| {
+            ((position / self.cell_size) as i32).clamp(0, count as i32 - 1)
+        };
+        (
+            cell(position.z, self.layers),
+            cell(position.y, self.rows),
+            cell(position.x, self.boids.columns),
+        )
+    }
+
+    /// Returns the row within the [`Grid`] of the given `row` in the given
+    /// `layer`, or an invalid row if either falls outside of the volume.
+    fn grid_row(&self, layer: i32, row: i32) -> i32 {
+        if (0..self.layers as i32).contains(&layer) && (0..self.rows as i32).contains(&row) {
+            layer * self.rows as i32 + row
+        } else {
+            Grid::<Boid3D>::EMPTY
+        }
+    }
+
+    /// Returns the row and column within the [`Grid`] of the cell containing
+    /// the `position`.
+    fn grid_cell(&self, position: Vector3) -> (i32, i32) {
+        let (layer, row, column) = self.cell_of(position);
+        (self.grid_row(layer, row), column)
+    }
+
+    /// Recreates the grid to fit the current size of the volume and cells,
+    /// with room for `count` boids.
+    fn resize_grid(&mut self, count: usize) {
+        let cells = |size: f32| ((size / self.cell_size).ceil() as usize).max(1);
+        let columns = cells(self.size.x);
+        self.rows = cells(self.size.y);
+        self.layers = cells(self.size.z);
+
+        let old_grid = std::mem::replace(
+            &mut self.boids,
+            Grid::new(count, columns, self.rows * self.layers),
+        );
+        for node in old_grid.values.iter() {
+            let (row, column) = self.grid_cell(node.val.position);
+            self.boids.add_val(node.val, row, column);
+        }
+    }
+}
+
+/// Returns the size of the volume for a screen of the given `width` and
+/// `height`, being as deep as it is high.
+fn volume_size(width: usize, height: usize) -> Vector3 {
+    Vector3::new(width as f32, height as f32, height as f32)
+}
+
+/// Calculate the air resistance encountered by the boid based on the `velocity`
+/// vector and the air resistance parameters in the `boid_settings`.
+fn drag(velocity: Vector3, boid_settings: &BoidSettings) -> Vector3 {
+    let k = boid_settings.friction_coefficient;
+    if boid_settings.squared_friction {
+        velocity * velocity.magnitude() * k
+    } else {
+        velocity * k
+    }
+}
+
+/// Create a random displacement vector based on the noise force in the
+/// `boid_settings` and the time `delta`, scaling like the 2D noise.
+fn rand_diffuse(boid_settings: &BoidSettings, delta: f32) -> Vector3 {
+    if delta > 0.0 && boid_settings.noise_force > 0.0 {
+        let force = boid_settings.noise_force / f32::sqrt(delta);
+        Vector3::new(
+            fastrand::f32() - 0.5,
+            fastrand::f32() - 0.5,
+            fastrand::f32() - 0.5,
+        ) * force
+    } else {
+        Vector3::ZERO
+    }
+}
+
+/// Limits the speed of the `velocity` between the minimum speed in the
+/// `boid_settings` and [`MAX_SPEED`].
+fn clip_speed(velocity: Vector3, boid_settings: &BoidSettings) -> Vector3 {
+    let speed = velocity.magnitude();
+    let min_speed = boid_settings.min_speed;
+    if speed < min_speed && speed != 0.0 {
+        velocity * (min_speed / speed)
+    } else if speed > MAX_SPEED {
+        velocity * (MAX_SPEED / speed)
+    } else {
+        velocity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resizing_to_nothing_keeps_positions_finite() {
+        let boid_settings = BoidSettings::new(2.0, 5.0, 0.01, 0.05, 0.05, 40, 40);
+        let mut flock = Flock3D::new(50, &boid_settings);
+        flock.update_window(0, 0);
+        flock.update_window(60, 30);
+        for boid in flock.boids.iter_all() {
+            let position = boid.position;
+            assert!(position.x.is_finite() && position.y.is_finite() && position.z.is_finite());
+            assert!(position.x <= 60.0 && position.y <= 30.0 && position.z <= 30.0);
+        }
+        assert_eq!(flock.boids.iter_all().count(), 50);
+    }
+}
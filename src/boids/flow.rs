@@ -0,0 +1,279 @@
+//! Vector fields of forces flowing across the world.
+//!
+//! # Flow
+//!
+//! Contains the [`FlowField`], which divides the world into square cells that
+//! each hold a force, filled in by one of the built-in [`FlowGenerator`]s.
+//! Every boid is pushed by the force at its position, interpolated between the
+//! surrounding cells. Unlike the gravity, which only points along the height
+//! of the world, the force can point in any direction and differ across the
+//! world.
+//!
+//! ## Curl noise
+//! The curl noise generator takes the curl of Perlin-style gradient noise,
+//! which is free of divergence, such that the boids are swirled around without
+//! piling up in any one place. The noise is animated by moving through it over
+//! time.
+
+use crate::vector2::Vector2;
+
+/// The width and height of a single flow cell in world units.
+pub const FLOW_CELL_SIZE: f32 = 4.0;
+/// The scale of the curl noise in noise units per world unit.
+const NOISE_SCALE: f32 = 0.04;
+/// How fast the curl noise changes in noise units per unit of time.
+const NOISE_SPEED: f32 = 0.1;
+/// The distance in noise units used to estimate the derivatives of the noise.
+const NOISE_EPSILON: f32 = 0.01;
+/// The distance from the center of the screen within which the vortex and
+/// sink weaken, preventing them from flipping direction abruptly at the center.
+const CORE_RADIUS: f32 = 10.0;
+
+/// The shape of the forces in a [`FlowField`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlowGenerator {
+    /// No forces at all
+    None,
+    /// The same force everywhere, blowing in the wind direction
+    Wind,
+    /// Forces circling around the center of the screen, clockwise for a
+    /// positive strength
+    Vortex,
+    /// Forces pointing towards the center of the screen, or away from it as a
+    /// source for a negative strength
+    Sink,
+    /// Animated swirls of curl noise
+    CurlNoise,
+}
+
+/// A layer of forces over the world, one for each cell.
+pub struct FlowField {
+    /// The generator filling in the forces.
+    pub generator: FlowGenerator,
+    /// The magnitude of the forces.
+    pub strength: f32,
+    /// The direction of the wind in degrees, clockwise from the right.
+    pub direction: f32,
+    /// Whether the field is shown as arrows.
+    pub visible: bool,
+    /// Row major force of each cell.
+    forces: Vec<Vector2>,
+    /// The amount of columns in the field.
+    pub columns: usize,
+    /// The amount of rows in the field.
+    pub rows: usize,
+    /// The center of the world covered by the field.
+    center: Vector2,
+    /// The time the curl noise has been animated for.
+    time: f32,
+}
+
+impl FlowField {
+    /// Creates a new [`FlowField`] without any forces, covering a world of the
+    /// given `width` and `height`.
+    pub fn new(width: usize, height: usize) -> FlowField {
+        let mut field = FlowField {
+            generator: FlowGenerator::None,
+            strength: 0.0,
+            direction: 0.0,
+            visible: false,
+            forces: Vec::new(),
+            columns: 0,
+            rows: 0,
+            center: Vector2::ZERO,
+            time: 0.0,
+        };
+        field.resize(width, height);
+        field
+    }
+
+    /// Resizes the field to cover a world of the given `width` and `height`,
+    /// generating the forces anew.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.columns = (width as f32 / FLOW_CELL_SIZE).ceil() as usize;
+        self.rows = (height as f32 / FLOW_CELL_SIZE).ceil() as usize;
+        self.center = Vector2::new(width as f32, height as f32) / 2.0;
+        self.generate();
+    }
+
+    /// Sets the `generator` of the forces, along with their `strength` and
+    /// the `direction` of the wind in degrees, generating the forces anew.
+    pub fn set_generator(
+        &mut self,
+        generator: FlowGenerator,
+        strength: f32,
+        direction: f32,
+    ) -> &mut Self {
+        self.generator = generator;
+        self.strength = strength;
+        self.direction = direction;
+        self.generate();
+        self
+    }
+
+    /// Sets whether the field is shown as arrows.
+    pub fn set_visible(&mut self, visible: bool) -> &mut Self {
+        self.visible = visible;
+        self
+    }
+
+    /// Whether the field has no forces.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.generator == FlowGenerator::None || self.strength == 0.0
+    }
+
+    /// Animates the field across the `delta` time frame, which only changes
+    /// the forces of the curl noise.
+    pub fn update(&mut self, delta: f32) {
+        if self.generator == FlowGenerator::CurlNoise && !self.is_empty() {
+            self.time += delta;
+            self.generate();
+        }
+    }
+
+    /// Returns the center of the cell at `row` and `column` in world coordinates.
+    #[inline]
+    pub fn cell_center(row: usize, column: usize) -> Vector2 {
+        Vector2::new(
+            (column as f32 + 0.5) * FLOW_CELL_SIZE,
+            (row as f32 + 0.5) * FLOW_CELL_SIZE,
+        )
+    }
+
+    /// Returns the force of the cell at `row` and `column`.
+    #[inline]
+    pub fn cell_force(&self, row: usize, column: usize) -> Vector2 {
+        self.forces[column + row * self.columns]
+    }
+
+    /// Returns the force at the `position`, interpolated bilinearly between
+    /// the centers of the surrounding cells. Positions outside of the field
+    /// take the force of the nearest cell.
+    pub fn force(&self, position: Vector2) -> Vector2 {
+        if self.is_empty() || self.forces.is_empty() {
+            return Vector2::ZERO;
+        }
+        let x = (position.x / FLOW_CELL_SIZE - 0.5).clamp(0.0, (self.columns - 1) as f32);
+        let y = (position.y / FLOW_CELL_SIZE - 0.5).clamp(0.0, (self.rows - 1) as f32);
+        let (column, row) = (x as usize, y as usize);
+        let (next_column, next_row) = (
+            (column + 1).min(self.columns - 1),
+            (row + 1).min(self.rows - 1),
+        );
+        let (tx, ty) = (x - column as f32, y - row as f32);
+        let top =
+            self.cell_force(row, column) * (1.0 - tx) + self.cell_force(row, next_column) * tx;
+        let bottom = self.cell_force(next_row, column) * (1.0 - tx)
+            + self.cell_force(next_row, next_column) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+
+    /// Fills in the force of every cell using the generator.
+    fn generate(&mut self) {
+        self.forces.clear();
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let force = self.generate_at(FlowField::cell_center(row, column));
+                self.forces.push(force);
+            }
+        }
+    }
+
+    /// Returns the force of the generator at the `position`.
+    fn generate_at(&self, position: Vector2) -> Vector2 {
+        let offset = position - self.center;
+        let core = (offset.magnitude() / CORE_RADIUS).min(1.0);
+        match self.generator {
+            FlowGenerator::None => Vector2::ZERO,
+            FlowGenerator::Wind => {
+                let angle = self.direction.to_radians();
+                Vector2::new(angle.cos(), angle.sin()) * self.strength
+            }
+            FlowGenerator::Vortex => {
+                let tangent = Vector2::new(-offset.y, offset.x).normalised();
+                tangent * (self.strength * core)
+            }
+            FlowGenerator::Sink => offset.normalised() * (-self.strength * core),
+            FlowGenerator::CurlNoise => {
+                curl_noise(position * NOISE_SCALE, self.time * NOISE_SPEED) * self.strength
+            }
+        }
+    }
+}
+
+/// Returns the curl of the gradient noise at the `position` in noise units at
+/// the given `time`, estimated through central differences.
+fn curl_noise(position: Vector2, time: f32) -> Vector2 {
+    let potential = |x: f32, y: f32| gradient_noise(x, y, time);
+    let (x, y) = (position.x, position.y);
+    let dx =
+        (potential(x + NOISE_EPSILON, y) - potential(x - NOISE_EPSILON, y)) / (2.0 * NOISE_EPSILON);
+    let dy =
+        (potential(x, y + NOISE_EPSILON) - potential(x, y - NOISE_EPSILON)) / (2.0 * NOISE_EPSILON);
+    Vector2::new(dy, -dx)
+}
+
+/// Returns the Perlin-style gradient noise at `x`, `y` and `z`, roughly
+/// between -1 and 1.
+fn gradient_noise(x: f32, y: f32, z: f32) -> f32 {
+    let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+    let (fx, fy, fz) = (x - x0, y - y0, z - z0);
+    let (ix, iy, iz) = (x0 as i32, y0 as i32, z0 as i32);
+
+    // The contribution of the gradient at each corner of the surrounding cube.
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        let gradient = corner_gradient(ix + dx, iy + dy, iz + dz);
+        gradient[0] * (fx - dx as f32)
+            + gradient[1] * (fy - dy as f32)
+            + gradient[2] * (fz - dz as f32)
+    };
+    let (u, v, w) = (fade(fx), fade(fy), fade(fz));
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    lerp(
+        lerp(
+            lerp(corner(0, 0, 0), corner(1, 0, 0), u),
+            lerp(corner(0, 1, 0), corner(1, 1, 0), u),
+            v,
+        ),
+        lerp(
+            lerp(corner(0, 0, 1), corner(1, 0, 1), u),
+            lerp(corner(0, 1, 1), corner(1, 1, 1), u),
+            v,
+        ),
+        w,
+    )
+}
+
+/// Smooths the fraction `t` within a cell of the noise, such that the noise
+/// has continuous derivatives across cells.
+#[inline]
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+/// Returns the pseudo-random gradient at the corner `x`, `y` and `z` of the
+/// noise, chosen from the twelve edges of a cube.
+fn corner_gradient(x: i32, y: i32, z: i32) -> [f32; 3] {
+    const GRADIENTS: [[f32; 3]; 12] = [
+        [1.0, 1.0, 0.0],
+        [-1.0, 1.0, 0.0],
+        [1.0, -1.0, 0.0],
+        [-1.0, -1.0, 0.0],
+        [1.0, 0.0, 1.0],
+        [-1.0, 0.0, 1.0],
+        [1.0, 0.0, -1.0],
+        [-1.0, 0.0, -1.0],
+        [0.0, 1.0, 1.0],
+        [0.0, -1.0, 1.0],
+        [0.0, 1.0, -1.0],
+        [0.0, -1.0, -1.0],
+    ];
+    let mut hash = (x as u32).wrapping_mul(0x8DA6_B343)
+        ^ (y as u32).wrapping_mul(0xD816_3841)
+        ^ (z as u32).wrapping_mul(0xCB1A_B31F);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x2C1B_3C6D);
+    hash ^= hash >> 12;
+    GRADIENTS[(hash % 12) as usize]
+}
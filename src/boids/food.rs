@@ -0,0 +1,31 @@
+//! Places in the world where boids regain energy.
+//!
+//! # Food
+//!
+//! Contains the definition of a [`FoodSource`], a circular area in which boids
+//! feed while the ecosystem is enabled in the
+//! [`BoidSettings`](super::BoidSettings).
+
+use crate::vector2::Vector2;
+
+/// A circular area in which boids regain their energy.
+#[derive(Debug, Clone, Copy)]
+pub struct FoodSource {
+    /// The center of the food source.
+    pub position: Vector2,
+    /// How close boids must come to the center to feed.
+    pub radius: f32,
+}
+
+impl FoodSource {
+    /// Creates a new [`FoodSource`] at `position` with the given `radius`.
+    pub fn new(position: Vector2, radius: f32) -> FoodSource {
+        FoodSource { position, radius }
+    }
+
+    /// Returns whether a boid at `position` is able to feed from the source.
+    #[inline]
+    pub fn reaches(&self, position: Vector2) -> bool {
+        (position - self.position).sqr_magnitude() < self.radius * self.radius
+    }
+}
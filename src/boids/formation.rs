@@ -0,0 +1,450 @@
+//! Formations of target points spelling out text or shapes.
+//!
+//! # Formation
+//!
+//! Contains the [`Formation`], which turns the lit pixels of a [`Bitmap`] into
+//! target points filling the screen at the resolution of the braille dots.
+//! Every boid is assigned one of these targets, to which it is softly
+//! attracted until the formation is released. Formations can also trace only
+//! the outlines of the lit pixels, see [`Formation::outline`].
+//!
+//! ## Assignment
+//! Until the targets are assigned, see [`Formation::assign`], every boid is
+//! given a target based on its index. Once assigned, the boids are matched to
+//! the targets close to them, such that they flow towards the nearby parts of
+//! a new formation instead of crossing the screen.
+//!
+//! ## Bitmaps
+//! Bitmaps are either rendered from text using a built-in 5x7 font, see
+//! [`Bitmap::from_text`], or loaded from a PBM image in either the plain
+//! (`P1`) or raw (`P4`) format, see [`Bitmap::load_pbm`].
+
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+};
+
+use crate::vector2::Vector2;
+
+/// The width of a glyph of the built-in font, excluding the spacing.
+const GLYPH_WIDTH: usize = 5;
+/// The height of a glyph of the built-in font.
+const GLYPH_HEIGHT: usize = 7;
+/// The distance between two braille dots in world units.
+const DOT_SIZE: f32 = 0.5;
+/// The part of the screen left empty on each side of the formation.
+const PADDING: f32 = 0.1;
+/// The most pixels a PBM image may hold, far more than any screen shows.
+const MAX_PBM_PIXELS: usize = 1 << 24;
+
+/// A monochrome image of lit and unlit pixels.
+#[derive(Debug, Clone)]
+pub struct Bitmap {
+    /// The amount of pixels in a row.
+    pub width: usize,
+    /// The amount of rows.
+    pub height: usize,
+    /// Row major pixels, where `true` is lit.
+    pixels: Vec<bool>,
+}
+
+impl Bitmap {
+    /// Renders the `text` on a single line using the built-in font. Lower case
+    /// letters are drawn as upper case, while unsupported characters are left
+    /// blank.
+    pub fn from_text(text: &str) -> Bitmap {
+        let glyphs: Vec<[u8; GLYPH_HEIGHT]> = text.chars().map(glyph).collect();
+        // A single column of spacing between the glyphs.
+        let width = (glyphs.len() * (GLYPH_WIDTH + 1)).saturating_sub(1);
+        let mut pixels = vec![false; width * GLYPH_HEIGHT];
+        for (index, rows) in glyphs.iter().enumerate() {
+            for (y, row) in rows.iter().enumerate() {
+                for x in 0..GLYPH_WIDTH {
+                    if row >> (GLYPH_WIDTH - 1 - x) & 1 == 1 {
+                        pixels[index * (GLYPH_WIDTH + 1) + x + y * width] = true;
+                    }
+                }
+            }
+        }
+        Bitmap {
+            width,
+            height: GLYPH_HEIGHT,
+            pixels,
+        }
+    }
+
+    /// Reads the PBM image at `file_path`, in either the plain (`P1`) or raw
+    /// (`P4`) format.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file can not be read or is
+    /// not a valid PBM image.
+    pub fn load_pbm(file_path: &str) -> Result<Bitmap> {
+        let bytes = fs::read(file_path)?;
+        parse_pbm(&bytes).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("{}: expected a PBM image in the P1 or P4 format", file_path),
+            )
+        })
+    }
+
+    /// Whether the pixel at `x` and `y` is lit.
+    #[inline]
+    pub fn is_lit(&self, x: usize, y: usize) -> bool {
+        self.pixels[x + y * self.width]
+    }
+}
+
+/// The target points of a [`Bitmap`], scaled to fit the screen.
+#[derive(Debug, Clone)]
+pub struct Formation {
+    /// The image that is formed.
+    bitmap: Bitmap,
+    /// The target points in world coordinates.
+    pub targets: Vec<Vector2>,
+    /// Whether the boids are released from the formation and flock freely.
+    pub released: bool,
+    /// Whether only the outlines of the lit pixels are formed.
+    outline: bool,
+    /// The index of the target assigned to each boid, empty if unassigned.
+    assignment: Vec<usize>,
+}
+
+impl Formation {
+    /// Creates a new [`Formation`] of the `bitmap`, centered on a screen of
+    /// the given `width` and `height`.
+    pub fn new(bitmap: Bitmap, width: usize, height: usize) -> Formation {
+        Formation::with_outline(bitmap, width, height, false)
+    }
+
+    /// Creates a new [`Formation`] of the outlines of the lit pixels in the
+    /// `bitmap`, centered on a screen of the given `width` and `height`.
+    pub fn outline(bitmap: Bitmap, width: usize, height: usize) -> Formation {
+        Formation::with_outline(bitmap, width, height, true)
+    }
+
+    /// Creates a new [`Formation`] of the `bitmap`, forming either the whole
+    /// lit pixels or only their `outline`.
+    fn with_outline(bitmap: Bitmap, width: usize, height: usize, outline: bool) -> Formation {
+        let mut formation = Formation {
+            bitmap,
+            targets: Vec::new(),
+            released: false,
+            outline,
+            assignment: Vec::new(),
+        };
+        formation.fit(width, height);
+        formation
+    }
+
+    /// Recalculates the targets to fit a screen of the given `width` and
+    /// `height`. Every lit pixel of the bitmap is filled with a target at each
+    /// braille dot it covers, or only at the dots along its unlit neighbours
+    /// when forming outlines. Clears the assignment of the targets.
+    pub fn fit(&mut self, width: usize, height: usize) {
+        self.targets.clear();
+        self.assignment.clear();
+        let bitmap = &self.bitmap;
+        if bitmap.width == 0 || bitmap.height == 0 {
+            return;
+        }
+        let scale = 1.0 - 2.0 * PADDING;
+        let pixel_size = (width as f32 * scale / bitmap.width as f32)
+            .min(height as f32 * scale / bitmap.height as f32);
+        let origin = Vector2::new(
+            (width as f32 - pixel_size * bitmap.width as f32) / 2.0,
+            (height as f32 - pixel_size * bitmap.height as f32) / 2.0,
+        );
+        let dots = ((pixel_size / DOT_SIZE) as usize).max(1);
+        let dot_size = pixel_size / dots as f32;
+
+        for y in 0..bitmap.height {
+            for x in 0..bitmap.width {
+                if !bitmap.is_lit(x, y) {
+                    continue;
+                }
+                let corner = origin + Vector2::new(x as f32, y as f32) * pixel_size;
+                for dot_y in 0..dots {
+                    for dot_x in 0..dots {
+                        if self.outline && !on_outline(bitmap, x, y, dot_x, dot_y, dots) {
+                            continue;
+                        }
+                        let offset = Vector2::new(dot_x as f32 + 0.5, dot_y as f32 + 0.5);
+                        self.targets.push(corner + offset * dot_size);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the target of the boid at `index` out of `count` boids, or
+    /// `None` if the formation is released or has no targets. Unless the
+    /// targets are assigned to exactly `count` boids, the boids are spread
+    /// evenly across the targets by their index, sharing them if there are
+    /// fewer targets than boids.
+    #[inline]
+    pub fn target(&self, index: usize, count: usize) -> Option<Vector2> {
+        if self.released || self.targets.is_empty() || index >= count {
+            return None;
+        }
+        if self.is_assigned(count) {
+            return Some(self.targets[self.assignment[index]]);
+        }
+        Some(self.targets[index * self.targets.len() / count])
+    }
+
+    /// Whether the targets are assigned to exactly `count` boids.
+    #[inline]
+    pub fn is_assigned(&self, count: usize) -> bool {
+        self.assignment.len() == count
+    }
+
+    /// Assigns the targets to the boids at the given `positions`, matching
+    /// every boid with a target near its current position.
+    ///
+    /// The boids are spread evenly across the targets as in
+    /// [`Formation::target`], after which both the boids and the targets are
+    /// sorted into as many columns as there are boids in a column. Within
+    /// each column, the boids take the targets in order from top to bottom.
+    pub fn assign(&mut self, positions: &[Vector2]) {
+        self.assignment.clear();
+        let count = positions.len();
+        if self.targets.is_empty() || count == 0 {
+            return;
+        }
+        let targets = &self.targets;
+        let mut slots: Vec<usize> = (0..count).map(|i| i * targets.len() / count).collect();
+        let mut boids: Vec<usize> = (0..count).collect();
+        slots.sort_by(|&a, &b| targets[a].x.total_cmp(&targets[b].x));
+        boids.sort_by(|&a, &b| positions[a].x.total_cmp(&positions[b].x));
+
+        self.assignment = vec![0; count];
+        let column_size = (count as f32).sqrt().ceil() as usize;
+        for (slot_column, boid_column) in slots
+            .chunks_mut(column_size)
+            .zip(boids.chunks_mut(column_size))
+        {
+            slot_column.sort_by(|&a, &b| targets[a].y.total_cmp(&targets[b].y));
+            boid_column.sort_by(|&a, &b| positions[a].y.total_cmp(&positions[b].y));
+            for (&slot, &boid) in slot_column.iter().zip(boid_column.iter()) {
+                self.assignment[boid] = slot;
+            }
+        }
+    }
+}
+
+/// Whether the dot at `dot_x` and `dot_y` out of `dots` dots along each side
+/// of the lit pixel at `x` and `y` in the `bitmap` lies along the edge of an
+/// unlit neighbouring pixel or the border of the bitmap.
+fn on_outline(
+    bitmap: &Bitmap,
+    x: usize,
+    y: usize,
+    dot_x: usize,
+    dot_y: usize,
+    dots: usize,
+) -> bool {
+    let unlit = |x: Option<usize>, y: Option<usize>| match (x, y) {
+        (Some(x), Some(y)) if x < bitmap.width && y < bitmap.height => !bitmap.is_lit(x, y),
+        _ => true,
+    };
+    (dot_x == 0 && unlit(x.checked_sub(1), Some(y)))
+        || (dot_x == dots - 1 && unlit(Some(x + 1), Some(y)))
+        || (dot_y == 0 && unlit(Some(x), y.checked_sub(1)))
+        || (dot_y == dots - 1 && unlit(Some(x), Some(y + 1)))
+}
+
+/// Parses the `bytes` of a PBM image, returning `None` if they are invalid or
+/// hold more than [`MAX_PBM_PIXELS`] pixels.
+fn parse_pbm(bytes: &[u8]) -> Option<Bitmap> {
+    let raw = match bytes.get(0..2)? {
+        b"P1" => false,
+        b"P4" => true,
+        _ => return None,
+    };
+    let mut position = 2;
+    let width = pbm_number(bytes, &mut position)?;
+    let height = pbm_number(bytes, &mut position)?;
+
+    let pixel_count = width
+        .checked_mul(height)
+        .filter(|&count| count <= MAX_PBM_PIXELS)?;
+
+    let mut pixels = Vec::with_capacity(pixel_count);
+    if raw {
+        // The rows are padded to whole bytes after a single whitespace.
+        let row_bytes = width.div_ceil(8);
+        let start = position + 1;
+        let end = start.checked_add(row_bytes.checked_mul(height)?)?;
+        let raster = bytes.get(start..end)?;
+        for row in raster.chunks(row_bytes.max(1)).take(height) {
+            for x in 0..width {
+                pixels.push(row[x / 8] >> (7 - x % 8) & 1 == 1);
+            }
+        }
+    } else {
+        while pixels.len() < pixel_count {
+            skip_pbm_whitespace(bytes, &mut position);
+            match bytes.get(position)? {
+                b'0' => pixels.push(false),
+                b'1' => pixels.push(true),
+                _ => return None,
+            }
+            position += 1;
+        }
+    }
+    Some(Bitmap {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Moves the `position` past any whitespace and comments in the `bytes`.
+fn skip_pbm_whitespace(bytes: &[u8], position: &mut usize) {
+    while let Some(&byte) = bytes.get(*position) {
+        if byte == b'#' {
+            while bytes.get(*position).is_some_and(|&byte| byte != b'\n') {
+                *position += 1;
+            }
+        } else if byte.is_ascii_whitespace() {
+            *position += 1;
+        } else {
+            break;
+        }
+    }
+}
+
+/// Reads the number at the `position` in the header of a PBM image, moving
+/// the `position` past it.
+fn pbm_number(bytes: &[u8], position: &mut usize) -> Option<usize> {
+    skip_pbm_whitespace(bytes, position);
+    let start = *position;
+    while bytes.get(*position).is_some_and(u8::is_ascii_digit) {
+        *position += 1;
+    }
+    std::str::from_utf8(&bytes[start..*position])
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Returns the rows of the glyph for the `character` in the built-in font,
+/// where the lowest five bits of each row are its pixels from left to right.
+fn glyph(character: char) -> [u8; GLYPH_HEIGHT] {
+    match character.to_ascii_uppercase() {
+        'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x0A, 0x04, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        '!' => [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04],
+        '?' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '\'' => [0x0C, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00],
+        _ => [0x00; GLYPH_HEIGHT],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_plain_pbm() {
+        let bitmap = parse_pbm(b"P1\n# comment\n3 2\n1 0 1\n010\n").expect("valid image");
+        assert_eq!((bitmap.width, bitmap.height), (3, 2));
+        assert!(bitmap.is_lit(0, 0) && !bitmap.is_lit(1, 0) && bitmap.is_lit(2, 0));
+        assert!(!bitmap.is_lit(0, 1) && bitmap.is_lit(1, 1) && !bitmap.is_lit(2, 1));
+    }
+
+    #[test]
+    fn parses_raw_pbm() {
+        let mut bytes = b"P4\n10 2\n".to_vec();
+        bytes.extend_from_slice(&[0b1000_0000, 0b0100_0000, 0b0000_0000, 0b1000_0000]);
+        let bitmap = parse_pbm(&bytes).expect("valid image");
+        assert_eq!((bitmap.width, bitmap.height), (10, 2));
+        assert!(bitmap.is_lit(0, 0) && bitmap.is_lit(9, 0) && bitmap.is_lit(8, 1));
+        assert_eq!(bitmap.pixels.iter().filter(|&&lit| lit).count(), 3);
+    }
+
+    #[test]
+    fn rejects_invalid_pbm() {
+        assert!(parse_pbm(b"P2\n1 1\n1\n").is_none());
+        assert!(parse_pbm(b"P1\n2 2\n1 0 1\n").is_none());
+        assert!(parse_pbm(b"P4\n16 2\n\xff\xff\xff").is_none());
+        assert!(parse_pbm(b"P4\n18446744073709551615 2\n").is_none());
+        assert!(parse_pbm(b"P1\n100000 100000\n1").is_none());
+    }
+
+    /// Returns the total distance between the boids at `positions` and their
+    /// targets in the `formation`.
+    fn total_distance(formation: &Formation, positions: &[Vector2]) -> f32 {
+        positions
+            .iter()
+            .enumerate()
+            .map(|(index, &position)| {
+                let target = formation.target(index, positions.len()).unwrap();
+                (target - position).magnitude()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn assign_matches_boids_with_nearby_targets() {
+        let mut formation = Formation::new(Bitmap::from_text("HI"), 80, 40);
+        let mut rng = fastrand::Rng::with_seed(3);
+        let mut positions = formation.targets.clone();
+        rng.shuffle(&mut positions);
+        let count = positions.len();
+
+        let unassigned = total_distance(&formation, &positions);
+        formation.assign(&positions);
+        assert!(formation.is_assigned(count));
+        assert!(total_distance(&formation, &positions) < unassigned * 0.25);
+
+        // Every target is taken by exactly one boid.
+        let mut taken = vec![false; count];
+        for index in 0..count {
+            let slot = formation.assignment[index];
+            assert!(!taken[slot]);
+            taken[slot] = true;
+        }
+    }
+}
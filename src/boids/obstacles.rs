@@ -0,0 +1,89 @@
+//! Static obstacles which boids steer around.
+//!
+//! # Obstacles
+//!
+//! Contains the definition of the [`Obstacle`] shapes that can be placed in
+//! the world, along with the geometric queries used by the simulation to
+//! steer boids around them and by the renderer to draw them.
+
+use crate::vector2::Vector2;
+
+/// A static shape in the world that boids avoid.
+#[derive(Debug, Clone, Copy)]
+pub enum Obstacle {
+    /// A filled circle around `center`.
+    Circle { center: Vector2, radius: f32 },
+    /// A filled axis-aligned rectangle spanning from `min` to `max`.
+    Rectangle { min: Vector2, max: Vector2 },
+    /// A thin line segment between `start` and `end`.
+    Segment { start: Vector2, end: Vector2 },
+}
+
+impl Obstacle {
+    /// Returns the signed distance from `position` to the edge of the obstacle,
+    /// which is negative inside the obstacle, along with the normal pointing
+    /// away from the obstacle.
+    pub fn distance(&self, position: Vector2) -> (f32, Vector2) {
+        match *self {
+            Obstacle::Circle { center, radius } => {
+                let diff = position - center;
+                (diff.magnitude() - radius, diff.normalised())
+            }
+            Obstacle::Rectangle { min, max } => {
+                let closest = Vector2::new(
+                    position.x.clamp(min.x, max.x),
+                    position.y.clamp(min.y, max.y),
+                );
+                let diff = position - closest;
+                if diff.sqr_magnitude() > 0.0 {
+                    return (diff.magnitude(), diff.normalised());
+                }
+                // Inside, push out through the closest side.
+                let sides = [
+                    (position.x - min.x, Vector2::new(-1.0, 0.0)),
+                    (max.x - position.x, Vector2::new(1.0, 0.0)),
+                    (position.y - min.y, Vector2::new(0.0, -1.0)),
+                    (max.y - position.y, Vector2::new(0.0, 1.0)),
+                ];
+                let (depth, normal) = sides
+                    .into_iter()
+                    .min_by(|a, b| a.0.total_cmp(&b.0))
+                    .unwrap();
+                (-depth, normal)
+            }
+            Obstacle::Segment { start, end } => {
+                let line = end - start;
+                let length = line.sqr_magnitude();
+                let t = if length > 0.0 {
+                    ((position - start).dot(&line) / length).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                let diff = position - (start + line * t);
+                (diff.magnitude(), diff.normalised())
+            }
+        }
+    }
+
+    /// Returns the top left and bottom right corners of the box bounding the obstacle.
+    pub fn bounds(&self) -> (Vector2, Vector2) {
+        match *self {
+            Obstacle::Circle { center, radius } => (center + -radius, center + radius),
+            Obstacle::Rectangle { min, max } => (min, max),
+            Obstacle::Segment { start, end } => (
+                Vector2::new(start.x.min(end.x), start.y.min(end.y)),
+                Vector2::new(start.x.max(end.x), start.y.max(end.y)),
+            ),
+        }
+    }
+
+    /// Whether the `position` is covered by the obstacle, where line segments
+    /// are considered to be `thickness` wide.
+    pub fn covers(&self, position: Vector2, thickness: f32) -> bool {
+        let (distance, _) = self.distance(position);
+        match self {
+            Obstacle::Segment { .. } => distance <= thickness * 0.5,
+            _ => distance <= 0.0,
+        }
+    }
+}
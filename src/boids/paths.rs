@@ -0,0 +1,140 @@
+//! Waypoint paths which groups of boids follow.
+//!
+//! # Paths
+//!
+//! Contains the definition of a [`Path`], a polyline or closed loop of
+//! waypoints, along with the loading of paths from a file.
+//!
+//! ## File format
+//! Paths are stored as plain text, with a single waypoint per line given by
+//! its `x` and `y` world coordinates separated by whitespace. Paths are
+//! separated by empty lines, and a path containing a line with the word
+//! `closed` forms a loop. Lines starting with `#` are ignored.
+//!
+//! ```text
+//! # Loop followed by group 0
+//! 20 10
+//! 60 10
+//! 40 40
+//! closed
+//!
+//! # Polyline followed by group 1
+//! 10 10
+//! 80 50
+//! ```
+
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+};
+
+use crate::vector2::Vector2;
+
+/// A sequence of waypoints, either ending at the last waypoint or looping
+/// back to the first.
+#[derive(Debug, Clone)]
+pub struct Path {
+    /// The waypoints in the order in which they are visited.
+    pub waypoints: Vec<Vector2>,
+    /// Whether the path loops back to the first waypoint.
+    pub closed: bool,
+}
+
+impl Path {
+    /// Creates a new [`Path`] through the given `waypoints`.
+    pub fn new(waypoints: Vec<Vector2>, closed: bool) -> Path {
+        Path { waypoints, closed }
+    }
+
+    /// Returns the waypoint at `index`, or `None` if an open path has been
+    /// completed.
+    #[inline]
+    pub fn waypoint(&self, index: usize) -> Option<Vector2> {
+        if self.closed && !self.waypoints.is_empty() {
+            Some(self.waypoints[index % self.waypoints.len()])
+        } else {
+            self.waypoints.get(index).copied()
+        }
+    }
+
+    /// Returns the index of the waypoint after the one at `index`.
+    #[inline]
+    pub fn next_index(&self, index: usize) -> usize {
+        if self.closed && !self.waypoints.is_empty() {
+            (index + 1) % self.waypoints.len()
+        } else {
+            (index + 1).min(self.waypoints.len())
+        }
+    }
+
+    /// Returns the pairs of waypoints forming each segment of the path.
+    pub fn segments(&self) -> impl Iterator<Item = (Vector2, Vector2)> + '_ {
+        let closing = if self.closed && self.waypoints.len() > 2 {
+            Some((*self.waypoints.last().unwrap(), self.waypoints[0]))
+        } else {
+            None
+        };
+        self.waypoints
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .chain(closing)
+    }
+
+    /// Reads all paths from the file at `file_path`, see the
+    /// [module documentation](self) for the file format.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file can not be read or
+    /// contains lines that are not waypoints.
+    pub fn load(file_path: &str) -> Result<Vec<Path>> {
+        let contents = fs::read_to_string(file_path)?;
+        let mut paths = Vec::new();
+        let mut current = Path::new(Vec::new(), false);
+
+        for (line_number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.starts_with('#') {
+                continue;
+            }
+            if line.is_empty() {
+                if !current.waypoints.is_empty() {
+                    paths.push(current);
+                }
+                current = Path::new(Vec::new(), false);
+                continue;
+            }
+            if line == "closed" {
+                current.closed = true;
+                continue;
+            }
+
+            let coordinates: Vec<f32> = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<std::result::Result<_, _>>()
+                .map_err(|_| invalid_line(file_path, line_number))?;
+            if let [x, y] = coordinates[..] {
+                current.waypoints.push(Vector2::new(x, y));
+            } else {
+                return Err(invalid_line(file_path, line_number));
+            }
+        }
+        if !current.waypoints.is_empty() {
+            paths.push(current);
+        }
+        Ok(paths)
+    }
+}
+
+/// Creates the error for an invalid line in a path file.
+fn invalid_line(file_path: &str, line_number: usize) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!(
+            "{}:{}: expected a waypoint as `x y`",
+            file_path,
+            line_number + 1
+        ),
+    )
+}
@@ -7,10 +7,15 @@
 //! of the settings, requiring extra options to be activated through the setting's
 //! factory pattern.
 
+use super::{
+    MAX_SAMPLES,
+    affinity::{AffinityMatrix, MAX_GROUPS},
+    arena::ArenaShape,
+};
 use crate::{grid::Grid, vector2::Vector2};
 
 /// Describes the behavior of a boid near/on the border
-#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BorderSettings {
     /// No special behavior
     None,
@@ -22,18 +27,322 @@ pub enum BorderSettings {
     BoundedHorizontal,
     /// Boids wrap around all borders
     Wrapping,
+    /// Boids bounce off all borders, mirroring their velocity
+    Reflect,
+    /// Boids are kept within an arena of the given shape, either by forcing
+    /// them away from its border or, if `hard`, by bouncing them off it
+    Arena { shape: ArenaShape, hard: bool },
+}
+
+impl BorderSettings {
+    /// Every border behavior, in the order in which they are listed.
+    pub const OPTIONS: [BorderSettings; 12] = [
+        BorderSettings::None,
+        BorderSettings::Bounded,
+        BorderSettings::BoundedVertical,
+        BorderSettings::BoundedHorizontal,
+        BorderSettings::Wrapping,
+        BorderSettings::Reflect,
+        BorderSettings::Arena {
+            shape: ArenaShape::Circle,
+            hard: false,
+        },
+        BorderSettings::Arena {
+            shape: ArenaShape::Circle,
+            hard: true,
+        },
+        BorderSettings::Arena {
+            shape: ArenaShape::Ellipse,
+            hard: false,
+        },
+        BorderSettings::Arena {
+            shape: ArenaShape::Ellipse,
+            hard: true,
+        },
+        BorderSettings::Arena {
+            shape: ArenaShape::RoundedRectangle,
+            hard: false,
+        },
+        BorderSettings::Arena {
+            shape: ArenaShape::RoundedRectangle,
+            hard: true,
+        },
+    ];
+
+    /// Whether boids wrap around the left and right borders.
+    #[inline]
+    pub fn wraps_horizontally(self) -> bool {
+        matches!(
+            self,
+            BorderSettings::Wrapping | BorderSettings::BoundedVertical
+        )
+    }
+
+    /// Whether boids wrap around the bottom and top borders.
+    #[inline]
+    pub fn wraps_vertically(self) -> bool {
+        matches!(
+            self,
+            BorderSettings::Wrapping | BorderSettings::BoundedHorizontal
+        )
+    }
+
+    /// Returns the name of the border behavior.
+    pub fn name(self) -> &'static str {
+        match self {
+            BorderSettings::None => "None",
+            BorderSettings::Bounded => "Bounded",
+            BorderSettings::BoundedVertical => "Bounded vertical",
+            BorderSettings::BoundedHorizontal => "Bounded horizontal",
+            BorderSettings::Wrapping => "Wrapping",
+            BorderSettings::Reflect => "Reflect",
+            BorderSettings::Arena { shape, hard } => match (shape, hard) {
+                (ArenaShape::Circle, false) => "Soft circle",
+                (ArenaShape::Circle, true) => "Hard circle",
+                (ArenaShape::Ellipse, false) => "Soft ellipse",
+                (ArenaShape::Ellipse, true) => "Hard ellipse",
+                (ArenaShape::RoundedRectangle, false) => "Soft rounded rectangle",
+                (ArenaShape::RoundedRectangle, true) => "Hard rounded rectangle",
+            },
+        }
+    }
+}
+
+/// Describes which other boids are considered neighbours of a boid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NeighbourMode {
+    /// All boids within the visible range
+    Metric,
+    /// The k nearest boids, independent of their distance
+    Topological,
+}
+
+/// Describes which of the boids within the surrounding cells a boid evaluates
+/// as neighbours
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NeighbourSampling {
+    /// Every boid, however dense the surrounding cells are
+    Exact,
+    /// At most the sample budget of boids, striding through the cells
+    /// proportionally to their density, which favours the boids early in each
+    /// cell
+    Strided,
+    /// At most the sample budget of boids, drawn uniformly through reservoir
+    /// sampling
+    Reservoir,
+}
+
+/// Describes the order in which the boids are updated
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpdateMode {
+    /// Boids are updated one by one, reacting to the already updated boids
+    Sequential,
+    /// All boids react to the same snapshot before any boid is moved
+    Synchronous,
+}
+
+/// Describes how the velocity and position of a boid are advanced in time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Integrator {
+    /// Moves with the velocity from before the update
+    ExplicitEuler,
+    /// Moves with the velocity from after the update
+    SemiImplicitEuler,
+    /// Averages the acceleration at the start and the end of the update
+    VelocityVerlet,
+    /// Uses the acceleration halfway through the update
+    Rk2,
+}
+
+/// Describes how the random noise on the movement of a boid varies over time,
+/// with every model spreading the velocity about as fast for the same force
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoiseModel {
+    /// Independent noise every update, jittering the boids
+    White,
+    /// A sideways push along a wander angle drifting smoothly per boid
+    Wander,
+    /// A force per boid drifting randomly while being pulled back to zero
+    OrnsteinUhlenbeck,
+    /// Rare bursts of heavy-tailed strength per boid, fading over time
+    Levy,
+}
+
+/// The largest weight given by the inverse [`Kernel`]s, preventing boids on
+/// top of each other from dividing by zero. Cohesion and alignment average by
+/// these weights, such that only separation is strengthened by them.
+const MAX_KERNEL_WEIGHT: f32 = 100.0;
+
+/// Describes how a rule weighs a neighbour by its distance within the range of
+/// the rule
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kernel {
+    /// Every neighbour within range weighs the same, up to a hard cut-off
+    Uniform,
+    /// The range divided by the distance, growing towards close neighbours
+    InverseDistance,
+    /// The square of the range divided by the distance, growing sharply
+    /// towards close neighbours
+    InverseSquare,
+    /// Fades linearly to zero at the edge of the range
+    Linear,
+    /// Fades smoothly to zero at the edge of the range
+    Smoothstep,
+}
+
+impl Kernel {
+    /// Every kernel, in the order in which they are listed.
+    pub const OPTIONS: [Kernel; 5] = [
+        Kernel::Uniform,
+        Kernel::InverseDistance,
+        Kernel::InverseSquare,
+        Kernel::Linear,
+        Kernel::Smoothstep,
+    ];
+
+    /// Returns the weight of a neighbour at the squared distance
+    /// `sqr_distance`, given the squared range `sqr_range` of the rule.
+    pub fn weight(self, sqr_distance: f32, sqr_range: f32) -> f32 {
+        let ratio = sqr_distance / sqr_range;
+        match self {
+            Kernel::Uniform => 1.0,
+            Kernel::InverseDistance => ratio.sqrt().recip().min(MAX_KERNEL_WEIGHT),
+            Kernel::InverseSquare => ratio.recip().min(MAX_KERNEL_WEIGHT),
+            Kernel::Linear => 1.0 - ratio.sqrt().clamp(0.0, 1.0),
+            Kernel::Smoothstep => {
+                let t = ratio.sqrt().clamp(0.0, 1.0);
+                1.0 - t * t * (3.0 - 2.0 * t)
+            }
+        }
+    }
+
+    /// Returns the name of the kernel.
+    pub fn name(self) -> &'static str {
+        match self {
+            Kernel::Uniform => "Uniform",
+            Kernel::InverseDistance => "Inverse distance",
+            Kernel::InverseSquare => "Inverse square",
+            Kernel::Linear => "Linear",
+            Kernel::Smoothstep => "Smoothstep",
+        }
+    }
+}
+
+/// Describes what the mouse does to the boids while the left mouse button is held
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MouseTool {
+    /// Pulls boids towards the mouse while it hovers over them, and pushes
+    /// them away like [`MouseTool::Repel`] while the left mouse button is held
+    Hover,
+    /// Pulls boids towards the mouse
+    Attract,
+    /// Pushes boids away from the mouse
+    Repel,
+    /// Spins boids around the mouse
+    Vortex,
+    /// Blows boids in the direction the mouse moves
+    Wind,
+    /// Adds boids around the mouse
+    Spawn,
+    /// Removes boids around the mouse
+    Delete,
+}
+
+impl MouseTool {
+    /// The amount of different tools.
+    pub const COUNT: usize = 7;
+
+    /// Returns the tool following this tool, wrapping around to the first.
+    pub fn next(self) -> MouseTool {
+        match self {
+            MouseTool::Hover => MouseTool::Attract,
+            MouseTool::Attract => MouseTool::Repel,
+            MouseTool::Repel => MouseTool::Vortex,
+            MouseTool::Vortex => MouseTool::Wind,
+            MouseTool::Wind => MouseTool::Spawn,
+            MouseTool::Spawn => MouseTool::Delete,
+            MouseTool::Delete => MouseTool::Hover,
+        }
+    }
+
+    /// Returns the name of the tool.
+    pub fn name(self) -> &'static str {
+        match self {
+            MouseTool::Hover => "Hover",
+            MouseTool::Attract => "Attract",
+            MouseTool::Repel => "Repel",
+            MouseTool::Vortex => "Vortex",
+            MouseTool::Wind => "Wind",
+            MouseTool::Spawn => "Spawn",
+            MouseTool::Delete => "Delete",
+        }
+    }
+
+    /// Whether the tool adds or removes boids instead of exerting a force.
+    pub fn changes_count(self) -> bool {
+        matches!(self, MouseTool::Spawn | MouseTool::Delete)
+    }
+}
+
+/// The strength, range and count of a single [`MouseTool`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ToolSettings {
+    /// The force of the tool
+    pub strength: f32,
+    /// From how far the mouse has an effect
+    pub range: f32,
+    /// The amount of boids per mouse event for the spawn and delete tools
+    pub count: usize,
+}
+
+/// The behavior of a single group of boids, resolved from the shared base in
+/// [`BoidSettings`] and the group's [`GroupOverrides`].
+#[derive(Debug, Clone, Copy)]
+pub struct GroupSettings {
+    /// Range within boids are repelled
+    pub protected_range: f32,
+    /// Range within boids are attracted and aligned
+    pub visible_range: f32,
+    /// The cohesion force modifier
+    pub cohesion: f32,
+    /// The separating force modifier
+    pub separation: f32,
+    /// The aligning force modifier
+    pub alignment: f32,
+    /// Min Speed
+    pub min_speed: f32,
+    /// Gravity force, can be negative
+    pub gravity: f32,
+
+    // Pre-calculations
+    pub sqr_protected_range: f32,
+    pub sqr_visible_range: f32,
+}
+
+/// Deviations of a single group from the shared base in [`BoidSettings`],
+/// where `None` falls back to the base value.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GroupOverrides {
+    pub protected_range: Option<f32>,
+    pub visible_range: Option<f32>,
+    pub cohesion: Option<f32>,
+    pub separation: Option<f32>,
+    pub alignment: Option<f32>,
+    pub min_speed: Option<f32>,
+    pub gravity: Option<f32>,
 }
 
 /// Contains the different settings relevant to the simulation of the boids.
 /// These include both required settings such as visibility range, and border settings
 /// , but also optional ones that can be changed using the implemented factory methods.
+#[derive(Clone)]
 pub struct BoidSettings {
-    /// Range within boids are repelled
+    /// Range within boids are repelled, shared by all groups without an override
     pub protected_range: f32,
-    /// Range within boids are attracted and aligned
+    /// Range within boids are attracted and aligned, shared by all groups without an override
     pub visible_range: f32,
 
-    // Basic Rules
+    // Basic Rules, shared by all groups without an override
     /// The cohesion force modifier
     pub cohesion: f32,
     /// The separating force modifier
@@ -41,6 +350,14 @@ pub struct BoidSettings {
     /// The aligning force modifier
     pub alignment: f32,
 
+    // Kernels, weighing neighbours by their distance
+    /// The kernel of the separation over the protected range
+    pub separation_kernel: Kernel,
+    /// The kernel of the cohesion over the visible range
+    pub cohesion_kernel: Kernel,
+    /// The kernel of the alignment over the visible range
+    pub alignment_kernel: Kernel,
+
     /// Window width
     pub width: usize,
     /// Window height
@@ -53,11 +370,48 @@ pub struct BoidSettings {
     /// How far away from the edges of the screen the border begins.
     pub margin: f32,
 
-    /// Gravity force, can be negative
+    /// The angle in degrees of the field of view in which boids perceive
+    /// neighbours, where the remainder forms a blind spot behind the boid
+    pub view_angle: f32,
+    /// Whether the perception of neighbours fades towards the edges of the field of view
+    pub view_falloff: bool,
+    /// Whether the neighbours are all boids within range or the k nearest ones
+    pub neighbour_mode: NeighbourMode,
+    /// The amount of neighbours considered in the topological neighbour mode
+    pub k_nearest: usize,
+    /// Whether all boids within the surrounding cells are evaluated or a
+    /// sample, see [`BoidSettings::effective_sampling`]
+    pub neighbour_sampling: NeighbourSampling,
+    /// The most boids evaluated by a boid when sampling its neighbours
+    pub sample_budget: usize,
+    /// Whether the boids are updated one by one or all at once
+    pub update_mode: UpdateMode,
+    /// The method of advancing the boids in time
+    pub integrator: Integrator,
+
+    /// The force pushing boids away from obstacles
+    pub avoid_force: f32,
+    /// How far ahead along their velocity boids look for obstacles
+    pub look_ahead: f32,
+
+    /// The force pushing boids away from painted walls
+    pub wall_force: f32,
+
+    /// How strongly boids seek the next waypoint on their group's path
+    pub path_force: f32,
+    /// How close boids must come to a waypoint before moving on to the next
+    pub waypoint_radius: f32,
+
+    /// How strongly boids are pulled towards their target in a formation
+    pub formation_force: f32,
+
+    /// Gravity force, can be negative, shared by all groups without an override
     pub gravity: f32,
     /// Random noise applied to boid's movement
     pub noise_force: f32,
-    /// Min Speed
+    /// How the random noise varies over time
+    pub noise_model: NoiseModel,
+    /// Min Speed, shared by all groups without an override
     pub min_speed: f32,
     /// Friction
     pub friction_coefficient: f32,
@@ -65,17 +419,79 @@ pub struct BoidSettings {
     pub squared_friction: bool,
 
     // Mouse
-    /// How much a boid is attracted to the mouse position, or repelled if negative
-    pub mouse_force: f32,
-    /// From how far the mouse has an effect
-    pub mouse_range: f32,
+    /// The tool applied by the mouse
+    pub mouse_tool: MouseTool,
+    /// The strength and range of each mouse tool
+    pub mouse_tools: [ToolSettings; MouseTool::COUNT],
+    /// Whether the current tool is being applied
+    pub mouse_active: bool,
+    /// Whether the mouse hovers over the boids, see [`MouseTool::Hover`]
+    pub mouse_hovering: bool,
     /// The current mouse position.
     pub mouse_position: Vector2,
+    /// The direction in which the mouse last moved
+    pub mouse_direction: Vector2,
+
+    // Predators
+    /// The amount of boids that hunt the others
+    pub predator_count: usize,
+    /// How strongly predators steer towards the densest nearby prey
+    pub chase_force: f32,
+    /// How strongly prey steer away from predators within their visible range
+    pub flee_force: f32,
+    /// The max speed of predators
+    pub predator_speed: f32,
+
+    // Ecosystem
+    /// Whether boids age, feed, reproduce and die
+    pub ecosystem: bool,
+    /// The energy lost per unit of time
+    pub energy_decay: f32,
+    /// The energy gained per unit of time while at a food source
+    pub feeding_rate: f32,
+    /// The energy gained per unit of time anywhere in the world
+    pub grazing_rate: f32,
+    /// The energy at which a boid splits its energy with a new offspring
+    pub reproduction_energy: f32,
+    /// The age at which boids die, or zero if boids do not die of age
+    pub max_age: f32,
+
+    // Crabs
+    /// Whether the boids walk along the bottom border and painted walls
+    /// instead of flying
+    pub crab_mode: bool,
+    /// The chance per unit of time that a walking crab hops
+    pub hop_rate: f32,
+    /// The upward speed with which crabs hop
+    pub hop_speed: f32,
+    /// How strongly crabs scatter away from the mouse
+    pub scatter_force: f32,
+    /// From how far crabs scatter away from the mouse
+    pub scatter_range: f32,
+
+    // Perching
+    /// Whether boids land and rest on surfaces
+    pub perching: bool,
+    /// The chance per unit of time that a boid near a surface lands on it
+    pub perch_rate: f32,
+    /// The longest time a boid stays perched
+    pub perch_time: f32,
+    /// The amount of flock-mates flying overhead that make a perched boid take
+    /// off, or zero if boids ignore their flock-mates
+    pub takeoff_count: usize,
+    /// From how far the mouse makes perched boids take off
+    pub disturb_range: f32,
+
+    // Groups
+    /// The overrides of the shared settings for each group
+    pub group_overrides: Vec<GroupOverrides>,
+    /// How strongly each group is attracted to or repelled by each other group
+    pub affinity: AffinityMatrix,
 
     // Pre-calculations
-    pub sqr_protected_range: f32,
-    pub sqr_visible_range: f32,
-    pub sqr_mouse_range: f32,
+    /// The resolved settings of each group
+    pub groups: Vec<GroupSettings>,
+    pub cos_half_view: f32,
 }
 
 impl BoidSettings {
@@ -89,31 +505,160 @@ impl BoidSettings {
         width: usize,
         height: usize,
     ) -> BoidSettings {
-        BoidSettings {
+        let mut boid_settings = BoidSettings {
             protected_range,
             visible_range,
             cohesion,
             separation,
             alignment,
+            separation_kernel: Kernel::Uniform,
+            cohesion_kernel: Kernel::Uniform,
+            alignment_kernel: Kernel::Uniform,
             width,
             height,
-            sqr_protected_range: protected_range * protected_range,
-            sqr_visible_range: visible_range * visible_range,
             border_settings: BorderSettings::None,
             turn_force: 0.0,
             margin: 0.0,
+            view_angle: 360.0,
+            view_falloff: false,
+            neighbour_mode: NeighbourMode::Metric,
+            k_nearest: 0,
+            neighbour_sampling: NeighbourSampling::Exact,
+            sample_budget: MAX_SAMPLES as usize,
+            update_mode: UpdateMode::Sequential,
+            integrator: Integrator::SemiImplicitEuler,
+            cos_half_view: -1.0,
+            avoid_force: 0.0,
+            look_ahead: 0.0,
+            wall_force: 0.0,
+            path_force: 0.0,
+            waypoint_radius: 0.0,
+            formation_force: 0.0,
             gravity: 0.0,
             min_speed: 0.0,
             noise_force: 0.0,
+            noise_model: NoiseModel::White,
             friction_coefficient: 0.0,
             squared_friction: false,
-            sqr_mouse_range: 0.0,
-            mouse_force: 0.0,
-            mouse_range: 0.0,
+            mouse_tool: MouseTool::Hover,
+            mouse_tools: [ToolSettings::default(); MouseTool::COUNT],
+            mouse_active: false,
+            mouse_hovering: false,
             mouse_position: Vector2::ZERO,
+            mouse_direction: Vector2::ZERO,
+            predator_count: 0,
+            chase_force: 0.0,
+            flee_force: 0.0,
+            predator_speed: 0.0,
+            ecosystem: false,
+            energy_decay: 0.0,
+            feeding_rate: 0.0,
+            grazing_rate: 0.0,
+            reproduction_energy: 0.0,
+            max_age: 0.0,
+            crab_mode: false,
+            hop_rate: 0.0,
+            hop_speed: 0.0,
+            scatter_force: 0.0,
+            scatter_range: 0.0,
+            perching: false,
+            perch_rate: 0.0,
+            perch_time: 0.0,
+            takeoff_count: 0,
+            disturb_range: 0.0,
+            group_overrides: vec![GroupOverrides::default()],
+            affinity: AffinityMatrix::identity(1),
+            groups: Vec::new(),
+        };
+        boid_settings.resolve_groups();
+        boid_settings
+    }
+
+    /// Resolves the settings of a group with the given `overrides` of the
+    /// shared settings.
+    fn resolve(&self, overrides: &GroupOverrides) -> GroupSettings {
+        let protected_range = overrides.protected_range.unwrap_or(self.protected_range);
+        let visible_range = overrides.visible_range.unwrap_or(self.visible_range);
+        GroupSettings {
+            protected_range,
+            visible_range,
+            cohesion: overrides.cohesion.unwrap_or(self.cohesion),
+            separation: overrides.separation.unwrap_or(self.separation),
+            alignment: overrides.alignment.unwrap_or(self.alignment),
+            min_speed: overrides.min_speed.unwrap_or(self.min_speed),
+            gravity: overrides.gravity.unwrap_or(self.gravity),
+            sqr_protected_range: protected_range * protected_range,
+            sqr_visible_range: visible_range * visible_range,
         }
     }
 
+    /// Recalculates the settings of each group from the shared base and the
+    /// group's overrides.
+    fn resolve_groups(&mut self) {
+        self.groups = self
+            .group_overrides
+            .iter()
+            .map(|overrides| self.resolve(overrides))
+            .collect();
+    }
+
+    /// Returns the settings shared by all groups, ignoring any overrides.
+    pub fn shared_group(&self) -> GroupSettings {
+        self.resolve(&GroupOverrides::default())
+    }
+
+    /// Returns the resolved settings of the given `group`.
+    #[inline]
+    pub fn group(&self, group: u8) -> &GroupSettings {
+        &self.groups[group as usize]
+    }
+
+    /// Returns the amount of groups the boids are divided in.
+    #[inline]
+    pub fn group_count(&self) -> u8 {
+        self.group_overrides.len() as u8
+    }
+
+    /// Returns the largest protected or visible range of any group.
+    pub fn max_range(&self) -> f32 {
+        self.groups.iter().fold(0.0, |max, group| {
+            max.max(group.visible_range).max(group.protected_range)
+        })
+    }
+
+    /// Sets the amount of groups of this [`BoidSettings`], at most
+    /// [`MAX_GROUPS`], keeping the overrides and affinities of the groups that
+    /// remain.
+    ///
+    /// ## Side-Effect
+    /// Redistributes the boids in the grid equally among the groups.
+    pub fn set_group_count(&mut self, group_count: u8, grid: &mut Grid<super::Boid>) -> &mut Self {
+        let group_count = group_count.clamp(1, MAX_GROUPS as u8);
+        self.group_overrides
+            .resize(group_count as usize, GroupOverrides::default());
+        self.affinity.resize(group_count as usize);
+        self.resolve_groups();
+        super::assign_groups(grid, group_count);
+        super::resize_grid(grid, self);
+        self
+    }
+
+    /// Sets the `overrides` of the shared settings for the given `group`.
+    ///
+    /// ## Side-Effect
+    /// Creates a new grid to fit any changed ranges.
+    pub fn set_group_overrides(
+        &mut self,
+        group: u8,
+        overrides: GroupOverrides,
+        grid: &mut Grid<super::Boid>,
+    ) -> &mut Self {
+        self.group_overrides[group as usize] = overrides;
+        self.resolve_groups();
+        super::resize_grid(grid, self);
+        self
+    }
+
     /// Update the window size within which the the boids are visible.
     ///
     /// ## Side-Effect
@@ -130,6 +675,27 @@ impl BoidSettings {
         self
     }
 
+    /// Sets the affinity of `group` towards the `other` group of this [`BoidSettings`].
+    pub fn set_affinity(&mut self, group: u8, other: u8, affinity: f32) -> &mut Self {
+        self.affinity.set(group, other, affinity);
+        self
+    }
+
+    /// Sets the entire `affinity` matrix of this [`BoidSettings`], also
+    /// setting the group count to the size of the matrix.
+    ///
+    /// ## Side-Effect
+    /// Redistributes the boids in the grid equally among the groups.
+    pub fn set_affinity_matrix(
+        &mut self,
+        affinity: AffinityMatrix,
+        grid: &mut Grid<super::Boid>,
+    ) -> &mut Self {
+        self.set_group_count(affinity.size() as u8, grid);
+        self.affinity = affinity;
+        self
+    }
+
     /// Set the protected range of this [`BoidSettings`].
     pub fn set_protected_range(
         &mut self,
@@ -137,7 +703,7 @@ impl BoidSettings {
         grid: &mut Grid<super::Boid>,
     ) -> &mut Self {
         self.protected_range = new_range;
-        self.sqr_protected_range = new_range;
+        self.resolve_groups();
         super::resize_grid(grid, self);
         self
     }
@@ -145,7 +711,7 @@ impl BoidSettings {
     /// Sets the visible range of this [`BoidSettings`].
     pub fn set_visible_range(&mut self, new_range: f32, grid: &mut Grid<super::Boid>) -> &mut Self {
         self.visible_range = new_range;
-        self.sqr_visible_range = new_range * new_range;
+        self.resolve_groups();
         super::resize_grid(grid, self);
         self
     }
@@ -153,24 +719,42 @@ impl BoidSettings {
     /// Sets the cohesion force of this [`BoidSettings`].
     pub fn set_cohesion_force(&mut self, new_force: f32) -> &mut Self {
         self.cohesion = new_force;
+        self.resolve_groups();
         self
     }
 
     /// Sets the separation force of this [`BoidSettings`].
     pub fn set_separation_force(&mut self, new_force: f32) -> &mut Self {
         self.separation = new_force;
+        self.resolve_groups();
         self
     }
 
     /// Sets the alignment force of this [`BoidSettings`].
     pub fn set_alignment_force(&mut self, new_force: f32) -> &mut Self {
         self.alignment = new_force;
+        self.resolve_groups();
+        self
+    }
+
+    /// Sets the kernels of this [`BoidSettings`], weighing the neighbours of
+    /// the `separation`, `cohesion` and `alignment` by their distance.
+    pub fn set_kernels(
+        &mut self,
+        separation: Kernel,
+        cohesion: Kernel,
+        alignment: Kernel,
+    ) -> &mut Self {
+        self.separation_kernel = separation;
+        self.cohesion_kernel = cohesion;
+        self.alignment_kernel = alignment;
         self
     }
 
     /// Sets the gravity of this [`BoidSettings`].
     pub fn set_gravity(&mut self, gravity: f32) -> &mut Self {
         self.gravity = gravity;
+        self.resolve_groups();
         self
     }
 
@@ -197,9 +781,96 @@ impl BoidSettings {
         self
     }
 
+    /// Sets the field of view of this [`BoidSettings`], consisting of the
+    /// `view_angle` in degrees and whether the perception has a `view_falloff`.
+    pub fn set_view(&mut self, view_angle: f32, view_falloff: bool) -> &mut Self {
+        // Prevent a zero width field of view from dividing by zero.
+        self.view_angle = view_angle.clamp(1.0, 360.0);
+        self.view_falloff = view_falloff;
+        self.cos_half_view = (self.view_angle.to_radians() * 0.5).cos();
+        self
+    }
+
+    /// Sets the neighbour mode of this [`BoidSettings`], along with the amount
+    /// of neighbours `k_nearest` considered in the topological mode.
+    pub fn set_neighbour_mode(
+        &mut self,
+        neighbour_mode: NeighbourMode,
+        k_nearest: usize,
+    ) -> &mut Self {
+        self.neighbour_mode = neighbour_mode;
+        self.k_nearest = k_nearest;
+        self
+    }
+
+    /// Sets the neighbour sampling of this [`BoidSettings`], along with the
+    /// `sample_budget` of boids evaluated when sampling.
+    pub fn set_neighbour_sampling(
+        &mut self,
+        neighbour_sampling: NeighbourSampling,
+        sample_budget: usize,
+    ) -> &mut Self {
+        self.neighbour_sampling = neighbour_sampling;
+        // Prevent an empty budget from ignoring every neighbour.
+        self.sample_budget = sample_budget.max(1);
+        self
+    }
+
+    /// Returns the neighbour sampling in effect, which is always exact in the
+    /// synchronous update mode, keeping its result independent of the order
+    /// in which the boids are stored.
+    #[inline]
+    pub fn effective_sampling(&self) -> NeighbourSampling {
+        match self.update_mode {
+            UpdateMode::Sequential => self.neighbour_sampling,
+            UpdateMode::Synchronous => NeighbourSampling::Exact,
+        }
+    }
+
+    /// Sets the update mode of this [`BoidSettings`].
+    pub fn set_update_mode(&mut self, update_mode: UpdateMode) -> &mut Self {
+        self.update_mode = update_mode;
+        self
+    }
+
+    /// Sets the integrator of this [`BoidSettings`].
+    pub fn set_integrator(&mut self, integrator: Integrator) -> &mut Self {
+        self.integrator = integrator;
+        self
+    }
+
+    /// Sets the obstacle avoidance of this [`BoidSettings`], consisting of
+    /// the `avoid_force` and the `look_ahead` distance.
+    pub fn set_obstacle_avoidance(&mut self, avoid_force: f32, look_ahead: f32) -> &mut Self {
+        self.avoid_force = avoid_force;
+        self.look_ahead = look_ahead;
+        self
+    }
+
+    /// Sets the wall force of this [`BoidSettings`].
+    pub fn set_wall_force(&mut self, wall_force: f32) -> &mut Self {
+        self.wall_force = wall_force;
+        self
+    }
+
+    /// Sets the path following of this [`BoidSettings`], consisting of the
+    /// `path_force` and the `waypoint_radius`.
+    pub fn set_path_following(&mut self, path_force: f32, waypoint_radius: f32) -> &mut Self {
+        self.path_force = path_force;
+        self.waypoint_radius = waypoint_radius;
+        self
+    }
+
+    /// Sets the formation force of this [`BoidSettings`].
+    pub fn set_formation_force(&mut self, formation_force: f32) -> &mut Self {
+        self.formation_force = formation_force;
+        self
+    }
+
     /// Sets the min speed of this [`BoidSettings`].
     pub fn set_min_speed(&mut self, min_speed: f32) -> &mut Self {
         self.min_speed = min_speed;
+        self.resolve_groups();
         self
     }
 
@@ -209,6 +880,12 @@ impl BoidSettings {
         self
     }
 
+    /// Sets the noise model of this [`BoidSettings`].
+    pub fn set_noise_model(&mut self, noise_model: NoiseModel) -> &mut Self {
+        self.noise_model = noise_model;
+        self
+    }
+
     /// Sets the friction of this [`BoidSettings`], including the friction
     /// coefficient and whether the friction scales linearly or squared with the
     /// speed, as defined by `squared_friction`.
@@ -218,17 +895,171 @@ impl BoidSettings {
         self
     }
 
-    /// Sets the mouse force of this [`BoidSettings`].
-    pub fn set_mouse_force(&mut self, mouse_force: f32, mouse_range: f32) -> &mut Self {
-        self.mouse_force = mouse_force;
-        self.mouse_range = mouse_range;
-        self.sqr_mouse_range = mouse_range * mouse_range;
+    /// Sets the mouse tool of this [`BoidSettings`].
+    pub fn set_mouse_tool(&mut self, mouse_tool: MouseTool) -> &mut Self {
+        self.mouse_tool = mouse_tool;
+        self
+    }
+
+    /// Sets the `strength` and `range` of the given mouse `tool` of this
+    /// [`BoidSettings`].
+    pub fn set_tool_settings(&mut self, tool: MouseTool, strength: f32, range: f32) -> &mut Self {
+        let settings = &mut self.mouse_tools[tool as usize];
+        settings.strength = strength;
+        settings.range = range;
+        self
+    }
+
+    /// Sets the `count` of boids spawned or deleted per mouse event and the
+    /// `range` of the given mouse `tool` of this [`BoidSettings`].
+    pub fn set_tool_count(&mut self, tool: MouseTool, count: usize, range: f32) -> &mut Self {
+        let settings = &mut self.mouse_tools[tool as usize];
+        settings.count = count;
+        settings.range = range;
+        self
+    }
+
+    /// Returns the strength, range and count of the current mouse tool.
+    #[inline]
+    pub fn tool_settings(&self) -> ToolSettings {
+        self.tool_settings_of(self.mouse_tool)
+    }
+
+    /// Returns the strength, range and count of the given mouse `tool`.
+    #[inline]
+    pub fn tool_settings_of(&self, tool: MouseTool) -> ToolSettings {
+        self.mouse_tools[tool as usize]
+    }
+
+    /// Sets whether the mouse tool of this [`BoidSettings`] is being applied.
+    pub fn set_mouse_active(&mut self, mouse_active: bool) -> &mut Self {
+        self.mouse_active = mouse_active;
+        self
+    }
+
+    /// Sets whether the mouse of this [`BoidSettings`] hovers over the boids.
+    pub fn set_mouse_hovering(&mut self, mouse_hovering: bool) -> &mut Self {
+        self.mouse_hovering = mouse_hovering;
         self
     }
 
-    /// Sets the mouse position of this [`BoidSettings`].
+    /// Returns the tool exerting a force through the mouse along with its
+    /// settings, if any. The hover tool attracts while the mouse hovers and
+    /// repels while it is active.
+    pub fn applied_mouse_tool(&self) -> Option<(MouseTool, ToolSettings)> {
+        let mouse_tool = match self.mouse_tool {
+            MouseTool::Hover if self.mouse_active => MouseTool::Repel,
+            MouseTool::Hover if self.mouse_hovering => MouseTool::Hover,
+            mouse_tool if self.mouse_active && !mouse_tool.changes_count() => mouse_tool,
+            _ => return None,
+        };
+        Some((mouse_tool, self.tool_settings_of(mouse_tool)))
+    }
+
+    /// Sets the predator count of this [`BoidSettings`].
+    ///
+    /// ## Side-Effect
+    /// Turns prey in the grid into predators or predators into prey until
+    /// the grid holds the predator count, or as many as it can.
+    pub fn set_predator_count(
+        &mut self,
+        predator_count: usize,
+        grid: &mut Grid<super::Boid>,
+    ) -> &mut Self {
+        super::assign_predators(grid, predator_count);
+        self.sync_predator_count(grid)
+    }
+
+    /// Sets the predator count of this [`BoidSettings`] to the amount of
+    /// predators in the `grid`, after boids were removed from it.
+    pub fn sync_predator_count(&mut self, grid: &Grid<super::Boid>) -> &mut Self {
+        self.predator_count = super::count_predators(grid);
+        self
+    }
+
+    /// Sets the chase and flee forces of this [`BoidSettings`].
+    pub fn set_predator_forces(&mut self, chase_force: f32, flee_force: f32) -> &mut Self {
+        self.chase_force = chase_force;
+        self.flee_force = flee_force;
+        self
+    }
+
+    /// Sets the predator speed of this [`BoidSettings`].
+    pub fn set_predator_speed(&mut self, predator_speed: f32) -> &mut Self {
+        self.predator_speed = predator_speed;
+        self
+    }
+
+    /// Sets the ecosystem of this [`BoidSettings`], consisting of whether it is
+    /// `enabled`, the `energy_decay` over time, the `feeding_rate` at food
+    /// sources, the `grazing_rate` elsewhere, the `reproduction_energy` and
+    /// the `max_age` of boids.
+    pub fn set_ecosystem(
+        &mut self,
+        enabled: bool,
+        energy_decay: f32,
+        feeding_rate: f32,
+        grazing_rate: f32,
+        reproduction_energy: f32,
+        max_age: f32,
+    ) -> &mut Self {
+        self.ecosystem = enabled;
+        self.energy_decay = energy_decay;
+        self.feeding_rate = feeding_rate;
+        self.grazing_rate = grazing_rate;
+        self.reproduction_energy = reproduction_energy;
+        self.max_age = max_age;
+        self
+    }
+
+    /// Sets the crab mode of this [`BoidSettings`], consisting of whether it is
+    /// `enabled`, the `hop_rate` and `hop_speed` of the crabs, and the
+    /// `scatter_force` and `scatter_range` with which they flee the mouse.
+    pub fn set_crab_mode(
+        &mut self,
+        enabled: bool,
+        hop_rate: f32,
+        hop_speed: f32,
+        scatter_force: f32,
+        scatter_range: f32,
+    ) -> &mut Self {
+        self.crab_mode = enabled;
+        self.hop_rate = hop_rate;
+        self.hop_speed = hop_speed;
+        self.scatter_force = scatter_force;
+        self.scatter_range = scatter_range;
+        self
+    }
+
+    /// Sets the perching of this [`BoidSettings`], consisting of whether it is
+    /// `enabled`, the `perch_rate` at which boids land, the longest
+    /// `perch_time`, and the `takeoff_count` of flock-mates and
+    /// `disturb_range` of the mouse making boids take off again.
+    pub fn set_perching(
+        &mut self,
+        enabled: bool,
+        perch_rate: f32,
+        perch_time: f32,
+        takeoff_count: usize,
+        disturb_range: f32,
+    ) -> &mut Self {
+        self.perching = enabled;
+        self.perch_rate = perch_rate;
+        self.perch_time = perch_time;
+        self.takeoff_count = takeoff_count;
+        self.disturb_range = disturb_range;
+        self
+    }
+
+    /// Sets the mouse position of this [`BoidSettings`], along with the
+    /// direction of the mouse if it moved.
     pub fn set_mouse_position(&mut self, x: f32, y: f32) -> &mut Self {
-        self.mouse_position = Vector2 { x, y };
+        let position = Vector2 { x, y };
+        let movement = position - self.mouse_position;
+        if movement.sqr_magnitude() > 0.0 {
+            self.mouse_direction = movement.normalised();
+        }
+        self.mouse_position = position;
         self
     }
 }
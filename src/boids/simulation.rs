@@ -9,13 +9,39 @@
 //! the boid to be adjusted.
 
 use super::{
-    CELLS_IN_RADIUS, MAX_SAMPLES, get_grid_position, settings::BoidSettings,
-    settings::BorderSettings,
+    BoidKind, CELLS_IN_RADIUS, FlightState,
+    environment::Environment,
+    get_grid_position,
+    settings::{
+        BoidSettings, BorderSettings, GroupSettings, Integrator, MouseTool, NeighbourMode,
+        NeighbourSampling, NoiseModel,
+    },
+    walls::{WALL_CELL_SIZE, WallLayer},
 };
 use crate::{grid::Grid, vector2::Vector2};
 
 // Max speed of boids
 const MAX_SPEED: f32 = 100.0;
+// Distance from which painted walls repel boids
+const WALL_RANGE: f32 = 4.0;
+// How much stronger gravity pulls on crabs than on flying boids
+const CRAB_WEIGHT: f32 = 25.0;
+// Distance above a surface from which boids can perch on it
+const PERCH_DISTANCE: f32 = 2.0;
+// Speed with which perched boids take off
+const TAKEOFF_SPEED: f32 = 10.0;
+// How fast the wander angle drifts, in radians per square root of time
+const WANDER_RATE: f32 = 1.0;
+// How fast the Ornstein-Uhlenbeck noise is pulled back to zero per unit of time
+const OU_REVERSION: f32 = 0.5;
+// Chance per unit of time of a boid starting a Lévy flight burst
+const LEVY_RATE: f32 = 0.05;
+// Exponent of the heavy tailed strength of the Lévy flight bursts
+const LEVY_EXPONENT: f32 = 1.5;
+// Largest strength of a Lévy flight burst relative to the noise force
+const LEVY_MAX: f32 = 20.0;
+// How fast Lévy flight bursts fade per unit of time
+const LEVY_DECAY: f32 = 0.5;
 
 /// Calculate the air resistance encountered by the boid based on the `velocity`
 /// vector and the air resistance parameters in the `boid_settings`. The
@@ -54,31 +80,151 @@ fn rand_diffuse(boid_settings: &BoidSettings, delta: f32) -> Vector2 {
     }
 }
 
-/// Gives the force exerted by the mouse. This force depends both on the mouse
-/// options in `boid_settings` and the normalised distance between the mouse and
-/// the given `position`.
+/// Returns the factor by which the noise force is scaled for the given
+/// `noise_model`, such that every model spreads the velocity of a boid about as
+/// fast as white noise of the same noise force. White noise adds a variance of
+/// a twelfth of the squared force per unit of time to each component.
+fn noise_scale(noise_model: NoiseModel) -> f32 {
+    match noise_model {
+        NoiseModel::White => 1.0,
+        // A push of which the angle drifts by a variance of a third of the
+        // squared rate per unit of time adds 6 push^2 / rate^2.
+        NoiseModel::Wander => WANDER_RATE / f32::sqrt(72.0),
+        // A force varying by the scale that reverts at the given rate adds
+        // 2 scale^2 / rate.
+        NoiseModel::OrnsteinUhlenbeck => f32::sqrt(OU_REVERSION / 24.0),
+        // Bursts fading at the given decay add
+        // rate * E[strength^2] * scale^2 / (2 decay^2).
+        NoiseModel::Levy => {
+            let tail = LEVY_MAX.powf(2.0 - LEVY_EXPONENT);
+            let second_moment = LEVY_EXPONENT * (tail - 1.0) / (2.0 - LEVY_EXPONENT) + tail;
+            LEVY_DECAY / f32::sqrt(6.0 * LEVY_RATE * second_moment)
+        }
+    }
+}
+
+/// Advances the noise state of the `boid` across the `delta` time frame,
+/// depending on the noise model in the `boid_settings`:
+/// - White: Keeps no state.
+/// - Wander: Drifts the wander angle in a random walk.
+/// - Ornstein-Uhlenbeck: Drifts the force randomly while pulling it back
+///   to zero, such that it varies by about the noise force.
+/// - Lévy: Fades the force, occasionally replacing it by a burst in a random
+///   direction with a heavy tailed strength.
+///
+/// The force of the coherent models is scaled by [`noise_scale`].
+fn advance_noise(boid: &mut super::Boid, boid_settings: &BoidSettings, delta: f32) {
+    let noise = &mut boid.noise;
+    let force = boid_settings.noise_force * noise_scale(boid_settings.noise_model);
+    match boid_settings.noise_model {
+        NoiseModel::White => (),
+        NoiseModel::Wander => {
+            noise.wander_angle += (fastrand::f32() * 2.0 - 1.0) * WANDER_RATE * f32::sqrt(delta);
+        }
+        NoiseModel::OrnsteinUhlenbeck => {
+            // Exact update of the process, stable for any delta.
+            let decay = f32::exp(-OU_REVERSION * delta);
+            let spread = force * f32::sqrt(1.0 - decay * decay);
+            noise.force = noise.force * decay + unit_noise() * spread;
+        }
+        NoiseModel::Levy => {
+            noise.force *= f32::exp(-LEVY_DECAY * delta);
+            if fastrand::f32() < LEVY_RATE * delta {
+                let strength = (1.0 - fastrand::f32())
+                    .powf(-1.0 / LEVY_EXPONENT)
+                    .min(LEVY_MAX);
+                let angle = fastrand::f32() * std::f32::consts::TAU;
+                noise.force = Vector2::new(angle.cos(), angle.sin()) * (force * strength);
+            }
+        }
+    }
+}
+
+/// Returns a random vector of which both components have a mean of zero and
+/// a variance of one.
+#[inline]
+fn unit_noise() -> Vector2 {
+    // Uniform noise between -0.5 and 0.5 has a variance of 1/12.
+    let scale = f32::sqrt(12.0);
+    Vector2::new(fastrand::f32() - 0.5, fastrand::f32() - 0.5) * scale
+}
+
+/// Gives the random force on the `boid` across the `delta` time frame, either
+/// drawn anew for white noise, see [`rand_diffuse`], or taken from the noise
+/// state of the boid, see [`advance_noise`]. The wander noise pushes the boid
+/// along its heading rotated by the wander angle, scaled by [`noise_scale`].
+fn noise_force(boid: &super::Boid, boid_settings: &BoidSettings, delta: f32) -> Vector2 {
+    match boid_settings.noise_model {
+        NoiseModel::White => rand_diffuse(boid_settings, delta),
+        NoiseModel::Wander => {
+            let heading = match boid.velocity.normalised() {
+                heading if heading.sqr_magnitude() > 0.0 => heading,
+                _ => Vector2::new(1.0, 0.0),
+            };
+            let (sin, cos) = boid.noise.wander_angle.sin_cos();
+            let direction = Vector2::new(
+                heading.x * cos - heading.y * sin,
+                heading.x * sin + heading.y * cos,
+            );
+            direction * (boid_settings.noise_force * noise_scale(NoiseModel::Wander))
+        }
+        NoiseModel::OrnsteinUhlenbeck | NoiseModel::Levy => boid.noise.force,
+    }
+}
+
+/// Gives the force exerted by the mouse while its tool is applied, see
+/// [`BoidSettings::applied_mouse_tool`]. This force depends both on the
+/// applied tool in `boid_settings` and the normalised distance between the
+/// mouse and the given `position`:
+/// - Hover and Attract: Pulls the boid towards the mouse.
+/// - Repel: Pushes the boid away from the mouse.
+/// - Vortex: Pushes the boid tangentially around the mouse.
+/// - Wind: Pushes the boid in the direction the mouse last moved in.
+///
+/// The spawn and delete tools do not exert any force.
 ///
 /// # Notes
 /// To improve the feeling of the mouse force and create a sort of rock in a stream
 /// effect, repelling mouse forces scale with the square of the normalised distance.
 fn mouse_force(position: Vector2, boid_settings: &BoidSettings) -> Vector2 {
-    if boid_settings.mouse_force == 0.0 {
+    let Some((mouse_tool, tool)) = boid_settings.applied_mouse_tool() else {
+        return Vector2::ZERO;
+    };
+    if tool.strength == 0.0 {
         return Vector2::ZERO;
     }
-    let mut diff = boid_settings.mouse_position - position;
+    let diff = boid_settings.mouse_position - position;
     let sqr_diff = diff.sqr_magnitude();
-    if sqr_diff < boid_settings.sqr_mouse_range {
+    let sqr_range = tool.range * tool.range;
+    if sqr_diff >= sqr_range {
+        return Vector2::ZERO;
+    }
+    let norm_diff = f32::sqrt(sqr_diff);
+    match mouse_tool {
+        MouseTool::Hover | MouseTool::Attract => diff * (tool.strength / tool.range),
         // Squared reppel force
-        if boid_settings.mouse_force < 0.0 {
-            let norm_diff = f32::sqrt(sqr_diff);
-            diff *= (1.0 - sqr_diff / boid_settings.sqr_mouse_range) / norm_diff
-                * boid_settings.mouse_force;
-        } else if boid_settings.mouse_force > 0.0 {
-            diff *= (1.0 / boid_settings.mouse_range) * boid_settings.mouse_force;
-        }
-        diff
-    } else {
-        Vector2::ZERO
+        MouseTool::Repel if norm_diff > 0.0 => {
+            diff * (-(1.0 - sqr_diff / sqr_range) / norm_diff * tool.strength)
+        }
+        MouseTool::Vortex if norm_diff > 0.0 => {
+            let tangent = Vector2::new(-diff.y, diff.x);
+            tangent * ((1.0 - norm_diff / tool.range) / norm_diff * tool.strength)
+        }
+        MouseTool::Wind => {
+            boid_settings.mouse_direction * ((1.0 - sqr_diff / sqr_range) * tool.strength)
+        }
+        _ => Vector2::ZERO,
+    }
+}
+
+/// Gives the force exerted by the nearest active attractor in the
+/// `environment` within range of the `position`, see [`Attractor::force`].
+///
+/// [`Attractor::force`]: super::Attractor::force
+fn attractor_force(position: Vector2, environment: &Environment) -> Vector2 {
+    match environment.nearest_attractor(position) {
+        Some(attractor) => attractor.force(position),
+        None => Vector2::ZERO,
     }
 }
 
@@ -86,11 +232,21 @@ fn mouse_force(position: Vector2, boid_settings: &BoidSettings) -> Vector2 {
 /// This force equals the border's force in `boid_settings` normal to the
 /// border, along with a small force in the direction of `velocity` parallel to
 /// the border, generally preventing other rules from cancelling out the border
-/// force.
+/// force. Soft arenas push boids within the margin of their border inwards
+/// along its normal in the same way.
 fn border_force(position: Vector2, velocity: Vector2, boid_settings: &BoidSettings) -> Vector2 {
     let mut accel = Vector2::ZERO;
     let turn_force = boid_settings.turn_force;
     let margin = boid_settings.margin;
+    if let BorderSettings::Arena { shape, hard: false } = boid_settings.border_settings {
+        let (distance, normal) =
+            shape.distance(position, boid_settings.width, boid_settings.height);
+        if distance > -margin {
+            let tangent = Vector2::new(-normal.y, normal.x);
+            let side = tangent.dot(&velocity).signum();
+            accel = (tangent * (side * 0.01) - normal) * turn_force;
+        }
+    }
     if let BorderSettings::Bounded | BorderSettings::BoundedHorizontal =
         boid_settings.border_settings
     {
@@ -116,51 +272,476 @@ fn border_force(position: Vector2, velocity: Vector2, boid_settings: &BoidSettin
     accel
 }
 
+/// Gives the force exerted by the obstacles in the `environment`.
+/// Obstacles are probed at the `position` and at points along the `velocity`
+/// up to the look ahead distance in `boid_settings`. Similar to the
+/// [`border_force`], the force is normal to the closest obstacle, with a small
+/// sideways push in the direction of travel.
+fn obstacle_force(
+    position: Vector2,
+    velocity: Vector2,
+    boid_settings: &BoidSettings,
+    environment: &Environment,
+) -> Vector2 {
+    const PROBES: [f32; 3] = [0.0, 0.5, 1.0];
+    let avoid_force = boid_settings.avoid_force;
+    if avoid_force == 0.0 || environment.obstacles.is_empty() {
+        return Vector2::ZERO;
+    }
+
+    let heading = velocity.normalised();
+    let mut accel = Vector2::ZERO;
+    for obstacle in environment.obstacles.iter() {
+        for probe in PROBES {
+            let probe_position = position + heading * (boid_settings.look_ahead * probe);
+            let (distance, normal) = obstacle.distance(probe_position);
+            if distance < boid_settings.protected_range {
+                let tangent = Vector2::new(-normal.y, normal.x);
+                accel += normal * avoid_force;
+                accel += tangent * (tangent.dot(&velocity).signum() * avoid_force * 0.01);
+                break;
+            }
+        }
+    }
+    accel
+}
+
+/// Gives the force exerted by the painted walls in the `environment`.
+/// Every wall cell within [`WALL_RANGE`] of the `position` pushes the boid
+/// away, with a force fading linearly with the distance to the cell.
+fn wall_force(
+    position: Vector2,
+    boid_settings: &BoidSettings,
+    environment: &Environment,
+) -> Vector2 {
+    let walls = &environment.walls;
+    if boid_settings.wall_force == 0.0 || walls.is_empty() {
+        return Vector2::ZERO;
+    }
+
+    let (row, column) = walls.cell_of(position);
+    let reach = WALL_RANGE.ceil() as i32;
+    let mut accel = Vector2::ZERO;
+    for r in (row - reach)..=(row + reach) {
+        for c in (column - reach)..=(column + reach) {
+            if !walls.is_wall_cell(r, c) {
+                continue;
+            }
+            let diff = position - WallLayer::cell_center(r, c);
+            let distance = diff.magnitude();
+            if distance < WALL_RANGE && distance > 0.0 {
+                accel += diff * ((1.0 - distance / WALL_RANGE) / distance);
+            }
+        }
+    }
+    accel * boid_settings.wall_force
+}
+
+/// Gives the force steering the `boid` towards its next waypoint on the path
+/// followed by its group in the `environment`. Boids without a path, or which
+/// have completed an open path, are not affected.
+fn path_force(
+    boid: &super::Boid,
+    boid_settings: &BoidSettings,
+    environment: &Environment,
+) -> Vector2 {
+    if boid_settings.path_force == 0.0 {
+        return Vector2::ZERO;
+    }
+    match environment
+        .path_for_group(boid.group)
+        .and_then(|path| path.waypoint(boid.waypoint))
+    {
+        Some(target) => (target - boid.position).normalised() * boid_settings.path_force,
+        None => Vector2::ZERO,
+    }
+}
+
+/// Gives the force pulling the `boid` at `index` out of `count` boids towards
+/// its target in the formation of the `environment`, see
+/// [`Formation::target`]. The force is critically damped by the velocity of
+/// the boid, letting it settle on its target instead of circling around it.
+///
+/// [`Formation::target`]: super::formation::Formation::target
+fn formation_force(
+    index: usize,
+    count: usize,
+    boid: &super::Boid,
+    boid_settings: &BoidSettings,
+    environment: &Environment,
+) -> Vector2 {
+    let force = boid_settings.formation_force;
+    if force == 0.0 {
+        return Vector2::ZERO;
+    }
+    match environment.formation_target(index, count) {
+        Some(target) => (target - boid.position) * force - boid.velocity * (2.0 * f32::sqrt(force)),
+        None => Vector2::ZERO,
+    }
+}
+
+/// Moves the `boid` on to the next waypoint of its group's path in the
+/// `environment` once it is within the waypoint radius of the current one.
+fn advance_waypoint(
+    boid: &mut super::Boid,
+    boid_settings: &BoidSettings,
+    environment: &Environment,
+) {
+    if let Some(path) = environment.path_for_group(boid.group)
+        && let Some(target) = path.waypoint(boid.waypoint)
+        && (target - boid.position).sqr_magnitude()
+            < boid_settings.waypoint_radius * boid_settings.waypoint_radius
+    {
+        boid.waypoint = path.next_index(boid.waypoint);
+    }
+}
+
 /// Wraps around the `position` given the border conditions in the `boid_settings`.
 fn wrapping(position: &mut Vector2, boid_settings: &BoidSettings) {
     // Wrap horizontally
-    if let BorderSettings::Wrapping | BorderSettings::BoundedVertical =
-        boid_settings.border_settings
-    {
+    if boid_settings.border_settings.wraps_horizontally() {
         position.x = position.x.rem_euclid(boid_settings.width as f32);
     }
 
     // Wrap vertically
-    if let BorderSettings::Wrapping | BorderSettings::BoundedHorizontal =
-        boid_settings.border_settings
-    {
+    if boid_settings.border_settings.wraps_vertically() {
         position.y = position.y.rem_euclid(boid_settings.height as f32);
     }
 }
 
-/// Returns the result of applying the three basic boid rules on the boid with the
-/// given `index` in the `grid`.
-/// These three rules are that each boid is:
-/// - Repelled from others that are too close.
-/// - Attracted to the average of the boids within their visible range.
-/// - Matching their velocity with other within their visible range.
-///
-/// , where the repelling and attracting ranges are given in the `boid_settings`.
-///
-/// # Return
-/// The function returns the [`Vector2`] with the rules induced force, along with
-/// the index of the boid before the given boid in the grid.
-fn boid_rules(
+/// Returns the offset from the `position` to the `other` position, taking the
+/// shortest way around the borders that wrap given the border conditions in
+/// the `boid_settings`.
+#[inline]
+fn wrapped_diff(position: Vector2, other: Vector2, boid_settings: &BoidSettings) -> Vector2 {
+    let mut diff = other - position;
+    let wrap_axis = |diff: f32, size: f32| diff - size * (diff / size).round();
+    if boid_settings.border_settings.wraps_horizontally() {
+        diff.x = wrap_axis(diff.x, boid_settings.width as f32);
+    }
+    if boid_settings.border_settings.wraps_vertically() {
+        diff.y = wrap_axis(diff.y, boid_settings.height as f32);
+    }
+    diff
+}
+
+/// Bounces the `position` and `velocity` off the border given the border
+/// conditions in the `boid_settings`. Positions beyond the border are
+/// mirrored back inside and the velocity is mirrored to point inwards, after
+/// which the position is kept within the border even if it overshot it by
+/// more than the size of the screen.
+fn reflect(position: &mut Vector2, velocity: &mut Vector2, boid_settings: &BoidSettings) {
+    let (width, height) = (boid_settings.width, boid_settings.height);
+    match boid_settings.border_settings {
+        BorderSettings::Reflect => {
+            let reflect_axis = |position: &mut f32, velocity: &mut f32, size: f32| {
+                if *position < 0.0 {
+                    *position = -*position;
+                    *velocity = velocity.abs();
+                } else if *position >= size {
+                    *position = 2.0 * size - *position;
+                    *velocity = -velocity.abs();
+                }
+                *position = position.clamp(0.0, size.next_down());
+            };
+            reflect_axis(&mut position.x, &mut velocity.x, width as f32);
+            reflect_axis(&mut position.y, &mut velocity.y, height as f32);
+        }
+        BorderSettings::Arena { shape, hard: true } => {
+            let (distance, normal) = shape.distance(*position, width, height);
+            if distance > 0.0 {
+                *position = shape.contain(*position - normal * (2.0 * distance), width, height);
+                let outward = velocity.dot(&normal);
+                if outward > 0.0 {
+                    *velocity -= normal * (2.0 * outward);
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Returns how strongly a boid moving along the `heading` perceives a
+/// neighbour at the offset `diff`. Neighbours in the blind spot behind the
+/// boid, outside of the view angle in `boid_settings`, are not perceived. With
+/// the view falloff enabled, the perception fades towards the edges of the
+/// field of view.
+#[inline]
+fn view_weight(heading: Vector2, diff: Vector2, boid_settings: &BoidSettings) -> f32 {
+    let full_view = boid_settings.view_angle >= 360.0 && !boid_settings.view_falloff;
+    if full_view || heading.sqr_magnitude() == 0.0 {
+        return 1.0;
+    }
+    let cos_angle = heading.dot(&diff.normalised());
+    let cos_half_view = boid_settings.cos_half_view;
+    if cos_angle < cos_half_view {
+        0.0
+    } else if boid_settings.view_falloff {
+        (cos_angle - cos_half_view) / (1.0 - cos_half_view)
+    } else {
+        1.0
+    }
+}
+
+// The total amount of cells that need to be scanned either horizontally
+// or vertically.
+const LOCAL_GRID_WIDTH: usize = CELLS_IN_RADIUS as usize * 2 + 1;
+
+// The total amount of cells that need to be scanned
+const LOCAL_GRID_SIZE: usize = LOCAL_GRID_WIDTH * LOCAL_GRID_WIDTH;
+
+// The furthest ring of cells searched for the k-nearest neighbours.
+const MAX_RING: i32 = CELLS_IN_RADIUS * 4;
+
+/// The fraction of the protected range, up to its edge, over which separation
+/// hands over to cohesion and alignment, such that a neighbour leaving the
+/// protected range is not suddenly attracted instead of avoided.
+const PROTECTED_BLEND: f32 = 0.25;
+
+/// The running sums of the contributions of the perceived neighbours to the
+/// rules of a single boid.
+struct Neighbourhood<'a> {
+    boid: &'a super::Boid,
+    group_settings: &'a GroupSettings,
+    heading: Vector2,
+    /// Whether the neighbours are the k-nearest, which are attracted and
+    /// aligned with independent of the visible range.
+    topological: bool,
+
+    avg: Vector2,
+    coh_count: f32,
+    align: Vector2,
+    align_count: f32,
+    sep: Vector2,
+    prot_count: f32,
+    flee: Vector2,
+    flee_count: f32,
+    // Prey within visible range per cell, used by predators to find the densest cell.
+    prey_sums: [Vector2; LOCAL_GRID_SIZE],
+    prey_counts: [u16; LOCAL_GRID_SIZE],
+}
+
+impl<'a> Neighbourhood<'a> {
+    fn new(boid: &'a super::Boid, boid_settings: &'a BoidSettings) -> Neighbourhood<'a> {
+        Neighbourhood {
+            boid,
+            group_settings: boid_settings.group(boid.group),
+            heading: boid.velocity.normalised(),
+            topological: boid_settings.neighbour_mode == NeighbourMode::Topological,
+            avg: Vector2::ZERO,
+            coh_count: 0.0,
+            align: Vector2::ZERO,
+            align_count: 0.0,
+            sep: Vector2::ZERO,
+            prot_count: 0.0,
+            flee: Vector2::ZERO,
+            flee_count: 0.0,
+            prey_sums: [Vector2::ZERO; LOCAL_GRID_SIZE],
+            prey_counts: [0; LOCAL_GRID_SIZE],
+        }
+    }
+
+    /// Adds the contribution of the `other` boid, located in the cell given by
+    /// `bin` within the local grid of cells surrounding the boid, if any.
+    fn add(&mut self, other: &super::Boid, bin: Option<usize>, boid_settings: &BoidSettings) {
+        let diff = wrapped_diff(self.boid.position, other.position, boid_settings);
+        let distance = diff.sqr_magnitude();
+        let visible = self.topological || distance < self.group_settings.sqr_visible_range;
+        let weight = view_weight(self.heading, diff, boid_settings);
+        if weight <= 0.0 {
+            return;
+        }
+        match (self.boid.kind, other.kind) {
+            (BoidKind::Prey, BoidKind::Predator) => {
+                if visible {
+                    self.flee -= diff.normalised() * weight;
+                    self.flee_count += weight;
+                }
+                return;
+            }
+            (BoidKind::Predator, BoidKind::Prey) => {
+                if visible && let Some(bin) = bin {
+                    self.prey_sums[bin] += diff;
+                    self.prey_counts[bin] += 1;
+                }
+                return;
+            }
+            _ => (),
+        }
+        let sqr_protected_range = self.group_settings.sqr_protected_range;
+        let separation = separation_share(distance, sqr_protected_range);
+        if separation > 0.0 {
+            // The inverse kernels push harder the closer the neighbour is.
+            let kernel = boid_settings
+                .separation_kernel
+                .weight(distance, sqr_protected_range);
+            let weight = weight * separation;
+            self.sep -= diff * (weight * kernel);
+            self.prot_count += weight * kernel.min(1.0);
+        }
+        if separation < 1.0 && visible {
+            // Scale attraction by the affinity between the groups, only
+            // aligning with groups the boid is attracted to.
+            let affinity = boid_settings.affinity.get(self.boid.group, other.group);
+            if affinity != 0.0 {
+                // Averaged by the summed kernel weights, such that close
+                // neighbours dominate the average without strengthening it.
+                let (cohesion, alignment) = self.visible_kernels(distance, boid_settings);
+                let weight = weight * (1.0 - separation);
+                self.avg += diff * (affinity * weight * cohesion);
+                self.coh_count += weight * cohesion;
+                if affinity > 0.0 {
+                    self.align += other.velocity * (affinity * weight * alignment);
+                    self.align_count += weight * alignment;
+                }
+            }
+        }
+    }
+
+    /// Returns the weights given by the cohesion and alignment kernels to a
+    /// neighbour at the squared `distance`. The k-nearest neighbours are
+    /// independent of the visible range, and thus weigh the same.
+    #[inline]
+    fn visible_kernels(&self, distance: f32, boid_settings: &BoidSettings) -> (f32, f32) {
+        if self.topological {
+            return (1.0, 1.0);
+        }
+        let range = self.group_settings.sqr_visible_range;
+        (
+            boid_settings.cohesion_kernel.weight(distance, range),
+            boid_settings.alignment_kernel.weight(distance, range),
+        )
+    }
+
+    /// Returns the force resulting from the rules applied to all added neighbours.
+    fn force(mut self, boid_settings: &BoidSettings) -> Vector2 {
+        let group_settings = self.group_settings;
+        if self.prot_count > 0.0 {
+            self.sep /= kernel_count(self.prot_count);
+        }
+
+        if self.boid.kind == BoidKind::Predator {
+            // Steer towards the average position of the prey in the densest cell.
+            let mut chase = Vector2::ZERO;
+            if let Some(densest) = (0..LOCAL_GRID_SIZE).max_by_key(|&i| self.prey_counts[i])
+                && self.prey_counts[densest] > 0
+            {
+                chase = (self.prey_sums[densest] / self.prey_counts[densest] as f32).normalised();
+            }
+            return chase * boid_settings.chase_force + self.sep * group_settings.separation;
+        }
+
+        if self.flee_count > 0.0 {
+            self.flee /= self.flee_count;
+        }
+
+        if self.coh_count > 0.0 {
+            self.avg /= kernel_count(self.coh_count);
+        }
+        if self.align_count > 0.0 {
+            self.align /= kernel_count(self.align_count);
+        }
+
+        self.avg * group_settings.cohesion
+            + self.align * group_settings.alignment
+            + self.sep * group_settings.separation
+            + self.flee * boid_settings.flee_force
+    }
+}
+
+/// Returns the amount of neighbours a rule averages over, given their summed
+/// weights `count`. Rules average over at least a single neighbour, such that
+/// a lone neighbour at the edge of a fading kernel, the field of view or the
+/// protected range barely contributes instead of counting in full.
+#[inline]
+fn kernel_count(count: f32) -> f32 {
+    count.max(1.0)
+}
+
+/// Returns the share of a neighbour at the squared `distance` given to
+/// separation rather than to cohesion and alignment, fading smoothly from one
+/// to zero over the outer [`PROTECTED_BLEND`] of the protected range.
+#[inline]
+fn separation_share(distance: f32, sqr_protected_range: f32) -> f32 {
+    let inner = 1.0 - PROTECTED_BLEND;
+    if distance >= sqr_protected_range {
+        0.0
+    } else if distance <= inner * inner * sqr_protected_range {
+        1.0
+    } else {
+        let t = ((distance / sqr_protected_range).sqrt() - inner) / PROTECTED_BLEND;
+        1.0 - t * t * (3.0 - 2.0 * t)
+    }
+}
+
+/// Returns the index of the cell at `row` and `column` within the local grid
+/// of cells surrounding the cell at `center_row` and `center_column`, or
+/// `None` if it falls outside of the local grid. The offsets between the cells
+/// take the shortest way around the rows and columns of the `grid` that wrap.
+#[inline]
+fn local_bin(
+    row: i32,
+    column: i32,
+    center_row: i32,
+    center_column: i32,
+    grid: &Grid<super::Boid>,
+) -> Option<usize> {
+    let wrap_offset = |offset: i32, count: usize, wraps: bool| {
+        let count = count as i32;
+        if wraps {
+            (offset + count / 2).rem_euclid(count) - count / 2
+        } else {
+            offset
+        }
+    };
+    let r_offset = wrap_offset(row - center_row, grid.rows, grid.wrap_rows) + CELLS_IN_RADIUS;
+    let c_offset =
+        wrap_offset(column - center_column, grid.columns, grid.wrap_columns) + CELLS_IN_RADIUS;
+    let width = LOCAL_GRID_WIDTH as i32;
+    if (0..width).contains(&r_offset) && (0..width).contains(&c_offset) {
+        Some((c_offset + r_offset * width) as usize)
+    } else {
+        None
+    }
+}
+
+/// Replaces the cells in the local grid given by their `indices` that repeat
+/// an earlier cell, or the center cell, by [`Grid::EMPTY`]. Cells only repeat
+/// once the rows or columns of the `grid` wrap around and the local grid is
+/// larger than the `grid` itself.
+fn skip_repeated_cells(indices: &mut [i32; LOCAL_GRID_SIZE], grid: &Grid<super::Boid>) {
+    let width = LOCAL_GRID_WIDTH;
+    let small =
+        (grid.wrap_rows && grid.rows < width) || (grid.wrap_columns && grid.columns < width);
+    if !small {
+        return;
+    }
+    let center = indices[LOCAL_GRID_SIZE / 2];
+    for i in 0..LOCAL_GRID_SIZE {
+        let repeated = if i == LOCAL_GRID_SIZE / 2 {
+            false
+        } else {
+            indices[i] == center || indices[..i].contains(&indices[i])
+        };
+        if repeated {
+            indices[i] = Grid::<super::Boid>::EMPTY;
+        }
+    }
+}
+
+/// Adds the boids within the cells surrounding the boid with the given `index`
+/// in the `grid` to the `neighbourhood`, either all of them or a sample within
+/// the sample budget, depending on the neighbour sampling of the
+/// `boid_settings`. The index of the boid before the given boid in its cell is
+/// stored in `prev_index`.
+fn metric_neighbours(
     index: usize,
     grid: &Grid<super::Boid>,
     boid_settings: &BoidSettings,
     prev_index: &mut i32,
-) -> Vector2 {
-    // The total amount of cells that need to be scanned either horizontally
-    // or vertically.
-    const LOCAL_GRID_WIDTH: usize = CELLS_IN_RADIUS as usize * 2 + 1;
-
-    // The total amount of cells that need to be scanned
-    const LOCAL_GRID_SIZE: usize = LOCAL_GRID_WIDTH * LOCAL_GRID_WIDTH;
-
-    let boid = &grid.values[index].val;
-    let position = boid.position;
-    let group = boid.group;
+    neighbourhood: &mut Neighbourhood,
+) {
+    let position = neighbourhood.boid.position;
     let (grid_row, grid_column) = get_grid_position(position, boid_settings, grid);
     let left_border = grid_column - CELLS_IN_RADIUS;
     let top_border = grid_row - CELLS_IN_RADIUS;
@@ -177,22 +758,128 @@ fn boid_rules(
             let other_column = left_border + c_offset;
             let i = (c_offset + r_offset * LOCAL_GRID_WIDTH as i32) as usize;
             indices[i] = grid.index_from_pos(other_row, other_column);
-            bins[i] = if let Some(grid_node) = grid.get_grid_node(other_row, other_column) {
-                grid_node.count as f32
-            } else {
-                0.0
-            } + if i == 0 { 0.0 } else { bins[i - 1] };
         }
     }
+    skip_repeated_cells(&mut indices, grid);
+    for i in 0..LOCAL_GRID_SIZE {
+        bins[i] = if indices[i] != Grid::<super::Boid>::EMPTY {
+            grid.grid[indices[i] as usize].count as f32
+        } else {
+            0.0
+        } + if i == 0 { 0.0 } else { bins[i - 1] };
+    }
+
+    // Every boid in the surrounding cells besides the boid itself.
+    let candidates = (bins[LOCAL_GRID_SIZE - 1] as usize).saturating_sub(1);
+    let budget = boid_settings.sample_budget;
+    match boid_settings.effective_sampling() {
+        NeighbourSampling::Strided => strided_neighbours(
+            index,
+            grid,
+            boid_settings,
+            &indices,
+            &bins,
+            prev_index,
+            neighbourhood,
+        ),
+        NeighbourSampling::Reservoir if candidates > budget => {
+            let mut reservoir = Reservoir::new(budget);
+            for_each_candidate(index, grid, &indices, prev_index, |boid_index, bin| {
+                reservoir.offer((boid_index, bin));
+            });
+            for (boid_index, bin) in reservoir.samples() {
+                let other_boid = grid.get_val(boid_index).unwrap();
+                neighbourhood.add(other_boid, Some(bin), boid_settings);
+            }
+        }
+        _ => for_each_candidate(index, grid, &indices, prev_index, |boid_index, bin| {
+            let other_boid = grid.get_val(boid_index).unwrap();
+            neighbourhood.add(other_boid, Some(bin), boid_settings);
+        }),
+    }
+}
+
+/// Calls `visit` with the index and local cell of every boid within the cells
+/// of the local grid given by their `indices`, besides the boid with the given
+/// `index`. The index of the boid before the given boid in its cell is stored
+/// in `prev_index`.
+fn for_each_candidate(
+    index: usize,
+    grid: &Grid<super::Boid>,
+    indices: &[i32; LOCAL_GRID_SIZE],
+    prev_index: &mut i32,
+    mut visit: impl FnMut(usize, usize),
+) {
+    for (current_bin, &cell_index) in indices.iter().enumerate() {
+        let mut local_prev_index = Grid::<super::Boid>::EMPTY;
+        for boid_index in grid.iter_from_index(cell_index) {
+            if boid_index == index {
+                *prev_index = local_prev_index;
+                continue;
+            }
+            local_prev_index = boid_index as i32;
+            visit(boid_index, current_bin);
+        }
+    }
+}
+
+/// A uniform sample of at most a fixed budget of the values offered to it,
+/// without knowing the amount of values up front.
+pub(super) struct Reservoir<T> {
+    samples: Vec<T>,
+    budget: usize,
+    seen: usize,
+}
+
+impl<T> Reservoir<T> {
+    /// Creates a new empty [`Reservoir`] keeping at most `budget` values.
+    pub(super) fn new(budget: usize) -> Reservoir<T> {
+        Reservoir {
+            samples: Vec::with_capacity(budget),
+            budget,
+            seen: 0,
+        }
+    }
+
+    /// Offers the `value` to the reservoir. Once the budget is full, the value
+    /// replaces a random sample with a chance decreasing with each offered
+    /// value, such that every value offered so far is kept equally likely.
+    pub(super) fn offer(&mut self, value: T) {
+        if self.seen < self.budget {
+            self.samples.push(value);
+        } else {
+            let replaced = fastrand::usize(..=self.seen);
+            if replaced < self.budget {
+                self.samples[replaced] = value;
+            }
+        }
+        self.seen += 1;
+    }
 
-    let mut avg = Vector2::ZERO;
-    let mut align = Vector2::ZERO;
-    let mut vis_count: u16 = 0;
-    let mut sep = Vector2::ZERO;
-    let mut prot_count: u16 = 0;
+    /// Returns the sampled values.
+    pub(super) fn samples(self) -> Vec<T> {
+        self.samples
+    }
+}
+
+/// Adds at most the sample budget of boids within the cells of the local grid
+/// to the `neighbourhood`, given by the `indices` of the cells and the
+/// cumulative boid count `bins`. The boids are sampled by striding through
+/// the cells proportionally to the density of each cell. The index of the boid
+/// before the boid with the given `index` in its cell is stored in
+/// `prev_index`.
+fn strided_neighbours(
+    index: usize,
+    grid: &Grid<super::Boid>,
+    boid_settings: &BoidSettings,
+    indices: &[i32; LOCAL_GRID_SIZE],
+    bins: &[f32; LOCAL_GRID_SIZE],
+    prev_index: &mut i32,
+    neighbourhood: &mut Neighbourhood,
+) {
     let mut prev_found = false;
 
-    let increment = (bins[LOCAL_GRID_SIZE - 1] / MAX_SAMPLES as f32).max(1.0);
+    let increment = (bins[LOCAL_GRID_SIZE - 1] / boid_settings.sample_budget as f32).max(1.0);
     let mut acc = 0.0;
 
     // Apply rules on surrounding cells
@@ -221,56 +908,123 @@ fn boid_rules(
             }
 
             let other_boid = grid.get_val(boid_index).unwrap();
-            let other_position = other_boid.position;
-            let diff = other_position - position;
-            let distance = diff.sqr_magnitude();
-            if distance < boid_settings.sqr_protected_range {
-                sep -= diff;
-                prot_count += 1;
-            } else if distance < boid_settings.sqr_visible_range && other_boid.group == group {
-                avg += diff;
-                align += other_boid.velocity;
-                vis_count += 1;
-            }
             acc += increment;
+            neighbourhood.add(other_boid, Some(current_bin), boid_settings);
         }
     }
+}
 
-    if prot_count > 0 {
-        sep /= prot_count as f32;
-    }
+/// Adds the k-nearest boids, as given in the `boid_settings`, to the boid with
+/// the given `index` in the `grid` to the `neighbourhood`. The index of the
+/// boid before the given boid in its cell is stored in `prev_index`.
+fn topological_neighbours(
+    index: usize,
+    grid: &Grid<super::Boid>,
+    boid_settings: &BoidSettings,
+    prev_index: &mut i32,
+    neighbourhood: &mut Neighbourhood,
+) {
+    let position = neighbourhood.boid.position;
+    let (grid_row, grid_column) = get_grid_position(position, boid_settings, grid);
+    *prev_index = grid.find_prev_index(index, grid_row, grid_column);
+
+    // The smallest side of a cell, limiting how close boids in other cells can be.
+    let cell_extent = (boid_settings.width as f32 / grid.columns as f32)
+        .min(boid_settings.height as f32 / grid.rows as f32);
+    let nearest = grid.k_nearest(
+        grid_row,
+        grid_column,
+        boid_settings.k_nearest,
+        MAX_RING,
+        cell_extent,
+        |other_index| {
+            if other_index == index {
+                None
+            } else {
+                let other = grid.values[other_index].val.position;
+                Some(wrapped_diff(position, other, boid_settings).sqr_magnitude())
+            }
+        },
+    );
 
-    if vis_count > 0 {
-        avg /= vis_count as f32;
-        align /= vis_count as f32;
+    for (other_index, _) in nearest {
+        let other_boid = &grid.values[other_index].val;
+        let (other_row, other_column) = get_grid_position(other_boid.position, boid_settings, grid);
+        let bin = local_bin(other_row, other_column, grid_row, grid_column, grid);
+        neighbourhood.add(other_boid, bin, boid_settings);
     }
+}
 
-    avg * boid_settings.cohesion + align * boid_settings.alignment + sep * boid_settings.separation
+/// Returns the result of applying the three basic boid rules on the boid with the
+/// given `index` in the `grid`.
+/// These three rules are that each boid is:
+/// - Repelled from others that are too close.
+/// - Attracted to the average of the boids within their visible range.
+/// - Matching their velocity with other within their visible range.
+///
+/// , where the repelling and attracting ranges are given by the settings of the
+/// boid's group in the `boid_settings`. Depending on the neighbour mode, the
+/// visible range is replaced by the k-nearest neighbours of the boid.
+///
+/// Only neighbours within the field of view of the boid are considered, see
+/// [`view_weight`].
+///
+/// The attraction and alignment towards boids of another group are scaled by the
+/// affinity between the groups, where a negative affinity repels instead.
+///
+/// ## Predators
+/// Prey additionally flee from every predator within their visible range,
+/// while predators ignore the basic rules with respect to prey and instead
+/// steer towards the cell containing the most prey within their visible range.
+///
+/// The rules are applied to the `boid` as given, which may differ from the
+/// boid stored at `index` when evaluating an intermediate state.
+///
+/// # Return
+/// The function returns the [`Vector2`] with the rules induced force, along with
+/// the index of the boid before the given boid in the grid.
+fn boid_rules(
+    index: usize,
+    boid: &super::Boid,
+    grid: &Grid<super::Boid>,
+    boid_settings: &BoidSettings,
+    prev_index: &mut i32,
+) -> Vector2 {
+    let mut neighbourhood = Neighbourhood::new(boid, boid_settings);
+    match boid_settings.neighbour_mode {
+        NeighbourMode::Metric => {
+            metric_neighbours(index, grid, boid_settings, prev_index, &mut neighbourhood)
+        }
+        NeighbourMode::Topological => {
+            topological_neighbours(index, grid, boid_settings, prev_index, &mut neighbourhood)
+        }
+    }
+    neighbourhood.force(boid_settings)
 }
 
-/// Updates the position of a boid given by `index` in the `grid`.
-/// This is done by applying all rules according to `boid_settings`, to
-/// change the current velocity and position of the boid. The scale of
-/// change in velocity and position are both dependent on the time `delta`.
-pub fn update_boid(
+/// Returns the acceleration of the `boid` stored at `index` in the `grid`,
+/// resulting from all rules and forces according to `boid_settings`, except
+/// for the noise. The index of the boid before the given boid in its cell is
+/// stored in `prev_index`.
+fn boid_acceleration(
     index: usize,
-    grid: &mut Grid<super::Boid>,
+    boid: &super::Boid,
+    grid: &Grid<super::Boid>,
     boid_settings: &BoidSettings,
-    delta: f32,
-) {
+    environment: &Environment,
+    prev_index: &mut i32,
+) -> Vector2 {
     // Basic boid forces
-    let boid = &grid.values[index].val;
     let position = boid.position;
     let velocity = boid.velocity;
-    let mut prev_index: i32 = Grid::<super::Boid>::EMPTY;
 
-    let mut accel = boid_rules(index, grid, boid_settings, &mut prev_index);
+    let mut accel = boid_rules(index, boid, grid, boid_settings, prev_index);
 
     // Gravity
-    accel.y += boid_settings.gravity;
+    accel.y += boid_settings.group(boid.group).gravity;
 
-    // Noise
-    accel += rand_diffuse(boid_settings, delta);
+    // Force of the flow field
+    accel += environment.flow.force(position);
 
     // Air Resistance
     accel -= drag(velocity, boid_settings);
@@ -278,31 +1032,397 @@ pub fn update_boid(
     // Mouse force
     accel += mouse_force(position, boid_settings);
 
+    // Force towards the nearest attractor
+    accel += attractor_force(position, environment);
+
     // Force on screen
     accel += border_force(position, velocity, boid_settings);
 
-    let boid = &mut grid.values[index].val;
-    // Update velocity based on differentials.
-    let mut velocity = boid.velocity;
-    velocity += accel * delta;
+    // Force around obstacles
+    accel += obstacle_force(position, velocity, boid_settings, environment);
+
+    // Force from painted walls
+    accel += wall_force(position, boid_settings, environment);
 
-    // Clipping.
+    // Force towards the next waypoint
+    accel += path_force(boid, boid_settings, environment);
+
+    // Force towards the target in the formation
+    accel += formation_force(index, grid.count, boid, boid_settings, environment);
+
+    accel
+}
+
+/// Limits the speed of the `velocity` of the `boid` between the minimum
+/// speed of its group and the maximum speed of its kind. Boids held
+/// `in_formation` have no minimum speed, allowing them to come to rest.
+fn clip_speed(
+    velocity: Vector2,
+    boid: &super::Boid,
+    boid_settings: &BoidSettings,
+    in_formation: bool,
+) -> Vector2 {
+    let max_speed = match boid.kind {
+        BoidKind::Prey => MAX_SPEED,
+        BoidKind::Predator => boid_settings.predator_speed,
+    };
     let speed = velocity.magnitude();
-    if speed < boid_settings.min_speed && speed != 0.0 {
-        let ratio = boid_settings.min_speed / speed;
-        velocity *= ratio;
-    } else if speed > MAX_SPEED {
+    let min_speed = if in_formation {
+        0.0
+    } else {
+        boid_settings.group(boid.group).min_speed
+    };
+    if speed < min_speed && speed != 0.0 {
+        let ratio = min_speed / speed;
+        velocity * ratio
+    } else if speed > max_speed {
         // Prevent speed overflows
-        let ratio = MAX_SPEED / speed;
-        velocity *= ratio;
+        let ratio = max_speed / speed;
+        velocity * ratio
+    } else {
+        velocity
+    }
+}
+
+/// Returns the position and velocity of the boid given by `index` in the
+/// `grid` after the time `delta`, integrated using the integrator in the
+/// `boid_settings`. The index of the boid before the given boid in its cell is
+/// stored in `prev_index`.
+///
+/// The integrators evaluating intermediate states do so against the current
+/// positions of the other boids, while the noise is drawn once per step.
+fn integrate(
+    index: usize,
+    grid: &Grid<super::Boid>,
+    boid_settings: &BoidSettings,
+    environment: &Environment,
+    delta: f32,
+    prev_index: &mut i32,
+) -> (Vector2, Vector2) {
+    let boid = grid.values[index].val;
+    let noise = noise_force(&boid, boid_settings, delta);
+    let accel =
+        boid_acceleration(index, &boid, grid, boid_settings, environment, prev_index) + noise;
+    // Acceleration at an intermediate state, ignoring its previous index.
+    let accel_at = |position: Vector2, velocity: Vector2| {
+        let trial = super::Boid {
+            position,
+            velocity,
+            ..boid
+        };
+        let mut ignored = Grid::<super::Boid>::EMPTY;
+        boid_acceleration(
+            index,
+            &trial,
+            grid,
+            boid_settings,
+            environment,
+            &mut ignored,
+        ) + noise
+    };
+
+    let position = boid.position;
+    let velocity = boid.velocity;
+    let in_formation = boid_settings.formation_force != 0.0
+        && environment.formation_target(index, grid.count).is_some();
+    match boid_settings.integrator {
+        Integrator::ExplicitEuler => {
+            let new_velocity =
+                clip_speed(velocity + accel * delta, &boid, boid_settings, in_formation);
+            (position + velocity * delta, new_velocity)
+        }
+        Integrator::SemiImplicitEuler => {
+            let new_velocity =
+                clip_speed(velocity + accel * delta, &boid, boid_settings, in_formation);
+            (position + new_velocity * delta, new_velocity)
+        }
+        Integrator::VelocityVerlet => {
+            let new_position = position + velocity * delta + accel * (0.5 * delta * delta);
+            let new_accel = accel_at(new_position, velocity + accel * delta);
+            let new_velocity = velocity + (accel + new_accel) * (0.5 * delta);
+            (
+                new_position,
+                clip_speed(new_velocity, &boid, boid_settings, in_formation),
+            )
+        }
+        Integrator::Rk2 => {
+            // Midpoint method
+            let mid_velocity = velocity + accel * (0.5 * delta);
+            let mid_accel = accel_at(position + velocity * (0.5 * delta), mid_velocity);
+            let new_velocity = clip_speed(
+                velocity + mid_accel * delta,
+                &boid,
+                boid_settings,
+                in_formation,
+            );
+            (position + mid_velocity * delta, new_velocity)
+        }
+    }
+}
+
+/// Returns the height at which crabs walk along the bottom border.
+#[inline]
+fn ground_level(boid_settings: &BoidSettings) -> f32 {
+    boid_settings.height as f32 - WALL_CELL_SIZE * 0.5
+}
+
+/// Whether a crab at the `position` stands on the bottom border or on top of a
+/// painted wall in the `environment`.
+fn on_surface(position: Vector2, boid_settings: &BoidSettings, environment: &Environment) -> bool {
+    position.y >= ground_level(boid_settings)
+        || environment
+            .walls
+            .is_wall(position + Vector2::new(0.0, WALL_CELL_SIZE))
+}
+
+/// Constrains the new position and velocity of the crab `boid` to the surface
+/// it walks on, after the time `delta`.
+///
+/// Crabs standing on a surface only follow the horizontal part of their
+/// velocity, applying the flocking rules along the surface, and occasionally
+/// hop at the hop rate in the `boid_settings`. Crabs in the air instead fall
+/// under the gravity of their group until they land on the bottom border or a
+/// painted wall. Crabs near the mouse scatter away from it along the surface.
+fn constrain_to_surface(
+    boid: &super::Boid,
+    (_, mut velocity): (Vector2, Vector2),
+    boid_settings: &BoidSettings,
+    environment: &Environment,
+    delta: f32,
+) -> (Vector2, Vector2) {
+    let position = boid.position;
+    if on_surface(position, boid_settings, environment) {
+        velocity.y = if fastrand::f32() < boid_settings.hop_rate * delta {
+            -boid_settings.hop_speed
+        } else {
+            0.0
+        };
+    } else {
+        let gravity = boid_settings.group(boid.group).gravity * CRAB_WEIGHT;
+        velocity = boid.velocity + Vector2::new(0.0, gravity * delta);
+    }
+
+    let diff = position - boid_settings.mouse_position;
+    let distance = diff.magnitude();
+    if distance < boid_settings.scatter_range {
+        let scatter = (1.0 - distance / boid_settings.scatter_range) * boid_settings.scatter_force;
+        velocity.x += diff.x.signum() * scatter * delta;
+    }
+    let mut new_position = position + velocity * delta;
+
+    // Land on painted walls instead of bouncing off them.
+    if velocity.y > 0.0
+        && let Some((free_position, _, true)) = environment.walls.first_hit(position, new_position)
+    {
+        let (row, _) = environment.walls.cell_of(free_position);
+        new_position = Vector2::new(free_position.x, WallLayer::cell_center(row, 0).y);
+        velocity.y = 0.0;
     }
+    let ground = ground_level(boid_settings);
+    if new_position.y >= ground {
+        new_position.y = ground;
+        velocity.y = 0.0;
+    }
+    (new_position, velocity)
+}
 
-    // Update position based on velocity.
-    let mut new_position = boid.position;
-    new_position += velocity * delta;
-    wrapping(&mut new_position, boid_settings);
+/// Returns the height of the surface at most `distance` below the `position`,
+/// being either the top of a painted wall in the `environment` or the bottom
+/// border, or `None` if there is no such surface.
+fn surface_below(
+    position: Vector2,
+    distance: f32,
+    boid_settings: &BoidSettings,
+    environment: &Environment,
+) -> Option<f32> {
+    let walls = &environment.walls;
+    let (row, column) = walls.cell_of(position);
+    let reach = (distance / WALL_CELL_SIZE).ceil() as i32;
+    for r in (row + 1)..=(row + reach) {
+        if walls.is_wall_cell(r, column) {
+            return Some(WallLayer::cell_center(r - 1, column).y);
+        }
+    }
+    let ground = ground_level(boid_settings);
+    (ground - position.y <= distance).then_some(ground)
+}
+
+/// Lands the flying prey `boid` on the surface below it at the perch rate in
+/// the `boid_settings` if it is near enough, see [`surface_below`]. The boid
+/// stays perched for a random duration up to the perch time.
+fn try_perch(
+    boid: &mut super::Boid,
+    boid_settings: &BoidSettings,
+    environment: &Environment,
+    delta: f32,
+) {
+    if boid.kind != BoidKind::Prey
+        || boid.flight != FlightState::Flying
+        || fastrand::f32() >= boid_settings.perch_rate * delta
+    {
+        return;
+    }
+    if let Some(surface) = surface_below(boid.position, PERCH_DISTANCE, boid_settings, environment)
+    {
+        boid.position.y = surface;
+        boid.velocity = Vector2::ZERO;
+        boid.flight = FlightState::Perched {
+            remaining: fastrand::f32() * boid_settings.perch_time,
+        };
+    }
+}
+
+/// Whether the perched boid at `index` in the `grid` is disturbed, either by
+/// the mouse applying a tool within the disturb range, see
+/// [`BoidSettings::applied_mouse_tool`], or by at least the takeoff count of
+/// flying flock-mates overhead within its visible range.
+fn disturbed(index: usize, grid: &Grid<super::Boid>, boid_settings: &BoidSettings) -> bool {
+    let boid = &grid.values[index].val;
+    let disturb_range = boid_settings.disturb_range;
+    if boid_settings.applied_mouse_tool().is_some()
+        && (boid.position - boid_settings.mouse_position).sqr_magnitude()
+            < disturb_range * disturb_range
+    {
+        return true;
+    }
+    if boid_settings.takeoff_count == 0 {
+        return false;
+    }
+
+    let sqr_visible_range = boid_settings.group(boid.group).sqr_visible_range;
+    let (grid_row, grid_column) = get_grid_position(boid.position, boid_settings, grid);
+    let mut count = 0;
+    for row in (grid_row - CELLS_IN_RADIUS)..=(grid_row + CELLS_IN_RADIUS) {
+        for column in (grid_column - CELLS_IN_RADIUS)..=(grid_column + CELLS_IN_RADIUS) {
+            for other_index in grid.iter_from_index(grid.index_from_pos(row, column)) {
+                let other = &grid.values[other_index].val;
+                let diff = wrapped_diff(boid.position, other.position, boid_settings);
+                if other.flight == FlightState::Flying
+                    && diff.y < 0.0
+                    && diff.sqr_magnitude() < sqr_visible_range
+                {
+                    count += 1;
+                    if count >= boid_settings.takeoff_count {
+                        return true;
+                    }
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Returns whether the boid at `index` in the `grid` takes off if it is
+/// perched, which it does once its time is up or when it is disturbed, see
+/// [`disturbed`], or `None` if the boid is flying.
+fn takes_off(index: usize, grid: &Grid<super::Boid>, boid_settings: &BoidSettings) -> Option<bool> {
+    let FlightState::Perched { remaining } = grid.values[index].val.flight else {
+        return None;
+    };
+    Some(remaining <= 0.0 || disturbed(index, grid, boid_settings))
+}
+
+/// Counts down the rest of the perched `boid` across the `delta` time frame,
+/// or makes it fly off if it must `take_off`, see [`takes_off`].
+///
+/// # Return
+/// Returns `true` if the boid is still perched and must not be moved.
+fn update_perched(boid: &mut super::Boid, take_off: bool, delta: f32) -> bool {
+    let FlightState::Perched { remaining } = boid.flight else {
+        return false;
+    };
+    if take_off {
+        boid.flight = FlightState::Flying;
+        boid.velocity =
+            Vector2::new(fastrand::f32() * 2.0 - 1.0, -1.0).normalised() * TAKEOFF_SPEED;
+        return false;
+    }
+    boid.flight = FlightState::Perched {
+        remaining: remaining - delta,
+    };
+    boid.previous_position = boid.position;
+    true
+}
+
+/// Moves a boid from the `position` to the `new_position` with the `velocity`,
+/// bouncing off any painted walls in the `environment` along the way instead
+/// of passing through them, and applying the borders in the `boid_settings`.
+///
+/// A boid caught within a wall, such as when it is painted over, is first
+/// moved out of it, see [`WallLayer::push_out`]. As wrapping around or
+/// reflecting off a border continues the path elsewhere, the part of the path
+/// after the border is tested separately, ending at the final position.
+fn bounce_off_walls(
+    position: Vector2,
+    new_position: &mut Vector2,
+    velocity: &mut Vector2,
+    boid_settings: &BoidSettings,
+    environment: &Environment,
+) {
+    let walls = &environment.walls;
+    let bounce = |new_position: &mut Vector2, velocity: &mut Vector2, from: Vector2| {
+        if let Some((free_position, horizontal, vertical)) = walls.first_hit(from, *new_position) {
+            *new_position = free_position;
+            if horizontal {
+                velocity.x = -velocity.x;
+            }
+            if vertical {
+                velocity.y = -velocity.y;
+            }
+        }
+    };
+
+    let free_position = walls.push_out(position);
+    *new_position += free_position - position;
+    let step = (*new_position - free_position).magnitude();
+    bounce(new_position, velocity, free_position);
+
+    let unbounded = *new_position;
+    wrapping(new_position, boid_settings);
+    reflect(new_position, velocity, boid_settings);
+    if walls.is_empty() || *new_position == unbounded {
+        return;
+    }
+    // The part of the path after the border, traced back from the final
+    // position along the new velocity.
+    let from = *new_position - velocity.normalised() * step;
+    bounce(new_position, velocity, from);
+    *new_position = walls.push_out(*new_position);
+}
+
+/// Moves the boid given by `index` in the `grid` to the new position with the
+/// new velocity, bouncing off any painted walls in between, see
+/// [`bounce_off_walls`], after which the boid is moved to its new cell. With
+/// perching enabled, the boid may land on a surface during the time `delta`,
+/// see [`try_perch`]. This requires the index of the boid before the given
+/// boid in its current cell as `prev_index`.
+fn apply_state(
+    index: usize,
+    grid: &mut Grid<super::Boid>,
+    (mut new_position, mut velocity): (Vector2, Vector2),
+    prev_index: i32,
+    boid_settings: &BoidSettings,
+    environment: &Environment,
+    delta: f32,
+) {
+    let boid = &mut grid.values[index].val;
+    let position = boid.position;
+    boid.previous_position = position;
+
+    bounce_off_walls(
+        position,
+        &mut new_position,
+        &mut velocity,
+        boid_settings,
+        environment,
+    );
     boid.velocity = velocity;
     boid.position = new_position;
+    advance_waypoint(boid, boid_settings, environment);
+    if boid_settings.perching {
+        try_perch(boid, boid_settings, environment, delta);
+        new_position = boid.position;
+    }
 
     // Update grid's linked list
     let (grid_row, grid_column) = get_grid_position(position, boid_settings, grid);
@@ -312,3 +1432,370 @@ pub fn update_boid(
 
     grid.link_val(index, new_grid_row, new_grid_column);
 }
+
+/// Updates the position of a boid given by `index` in the `grid`.
+/// This is done by applying all rules according to `boid_settings`, to
+/// change the current velocity and position of the boid. The scale of
+/// change in velocity and position are both dependent on the time `delta`.
+/// In crab mode, the boid is kept on the surface it walks on, see
+/// [`constrain_to_surface`], while perched boids are not moved at all, see
+/// [`update_perched`].
+pub fn update_boid(
+    index: usize,
+    grid: &mut Grid<super::Boid>,
+    boid_settings: &BoidSettings,
+    environment: &Environment,
+    delta: f32,
+) {
+    if let Some(take_off) = takes_off(index, grid, boid_settings)
+        && update_perched(&mut grid.values[index].val, take_off, delta)
+    {
+        return;
+    }
+    advance_noise(&mut grid.values[index].val, boid_settings, delta);
+    let mut prev_index: i32 = Grid::<super::Boid>::EMPTY;
+    let mut state = integrate(
+        index,
+        grid,
+        boid_settings,
+        environment,
+        delta,
+        &mut prev_index,
+    );
+    if boid_settings.crab_mode {
+        state = constrain_to_surface(
+            &grid.values[index].val,
+            state,
+            boid_settings,
+            environment,
+            delta,
+        );
+    }
+    apply_state(
+        index,
+        grid,
+        state,
+        prev_index,
+        boid_settings,
+        environment,
+        delta,
+    );
+}
+
+/// Updates the positions of all boids in the `grid` at once, see
+/// [`update_boid`]. Unlike updating the boids one by one, the new states of
+/// all boids are determined before any boid is moved, preventing boids from
+/// reacting to the already updated positions of other boids. Which perched
+/// boids take off is likewise determined before any of them does, and every
+/// neighbour is evaluated, see [`BoidSettings::effective_sampling`], such that
+/// the result does not depend on the order in which the boids are stored.
+pub fn update_boids_synchronous(
+    grid: &mut Grid<super::Boid>,
+    boid_settings: &BoidSettings,
+    environment: &Environment,
+    delta: f32,
+) {
+    let take_offs: Vec<Option<bool>> = (0..grid.values.len())
+        .map(|index| takes_off(index, grid, boid_settings))
+        .collect();
+    let perched: Vec<bool> = grid
+        .values
+        .iter_mut()
+        .zip(take_offs)
+        .map(|(node, take_off)| {
+            take_off.is_some_and(|take_off| update_perched(&mut node.val, take_off, delta))
+        })
+        .collect();
+    // The noise of each boid only depends on the boid itself.
+    for (node, _) in grid
+        .values
+        .iter_mut()
+        .zip(&perched)
+        .filter(|(_, perched)| !**perched)
+    {
+        advance_noise(&mut node.val, boid_settings, delta);
+    }
+    let mut prev_index: i32 = Grid::<super::Boid>::EMPTY;
+    let states: Vec<(Vector2, Vector2)> = (0..grid.values.len())
+        .map(|index| {
+            if perched[index] {
+                let boid = &grid.values[index].val;
+                return (boid.position, boid.velocity);
+            }
+            let state = integrate(
+                index,
+                grid,
+                boid_settings,
+                environment,
+                delta,
+                &mut prev_index,
+            );
+            if boid_settings.crab_mode {
+                constrain_to_surface(
+                    &grid.values[index].val,
+                    state,
+                    boid_settings,
+                    environment,
+                    delta,
+                )
+            } else {
+                state
+            }
+        })
+        .collect();
+
+    for (index, state) in states.into_iter().enumerate() {
+        // Earlier boids may have left or joined the cell since the accelerations were determined.
+        let (grid_row, grid_column) =
+            get_grid_position(grid.values[index].val.position, boid_settings, grid);
+        let prev_index = grid.find_prev_index(index, grid_row, grid_column);
+        apply_state(
+            index,
+            grid,
+            state,
+            prev_index,
+            boid_settings,
+            environment,
+            delta,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::boids::Kernel;
+
+    fn settings(border_settings: BorderSettings) -> BoidSettings {
+        let mut boid_settings = BoidSettings::new(2.0, 5.0, 0.01, 0.05, 0.05, 20, 20);
+        boid_settings.set_border(border_settings);
+        boid_settings
+    }
+
+    /// An environment of 20 by 20 with a vertical wall in the first column.
+    fn walled_environment() -> Environment {
+        let mut environment = Environment::new(20, 20);
+        for row in 0..20 {
+            environment.walls.paint(WallLayer::cell_center(row, 0), 0.1);
+        }
+        environment
+    }
+
+    #[test]
+    fn wrapping_onto_wall_bounces() {
+        let boid_settings = settings(BorderSettings::Wrapping);
+        let environment = walled_environment();
+        let position = Vector2::new(19.8, 10.5);
+        let mut new_position = Vector2::new(20.6, 10.5);
+        let mut velocity = Vector2::new(4.0, 0.0);
+        bounce_off_walls(
+            position,
+            &mut new_position,
+            &mut velocity,
+            &boid_settings,
+            &environment,
+        );
+        assert!(!environment.walls.is_wall(new_position));
+        assert!(velocity.x < 0.0);
+    }
+
+    #[test]
+    fn reflecting_onto_wall_bounces() {
+        let boid_settings = settings(BorderSettings::Reflect);
+        let mut environment = Environment::new(20, 20);
+        environment.walls.paint(WallLayer::cell_center(6, 0), 0.1);
+        // Crosses the left border, reflecting into the wall cell.
+        let position = Vector2::new(0.3, 5.7);
+        let mut new_position = Vector2::new(-0.5, 6.5);
+        let mut velocity = Vector2::new(-4.0, 4.0);
+        bounce_off_walls(
+            position,
+            &mut new_position,
+            &mut velocity,
+            &boid_settings,
+            &environment,
+        );
+        assert!(!environment.walls.is_wall(new_position));
+    }
+
+    #[test]
+    fn caught_within_wall_is_pushed_out() {
+        let boid_settings = settings(BorderSettings::None);
+        let environment = walled_environment();
+        let position = Vector2::new(0.5, 10.5);
+        let mut new_position = Vector2::new(0.3, 10.5);
+        let mut velocity = Vector2::new(-1.0, 0.0);
+        bounce_off_walls(
+            position,
+            &mut new_position,
+            &mut velocity,
+            &boid_settings,
+            &environment,
+        );
+        assert!(!environment.walls.is_wall(new_position));
+    }
+
+    #[test]
+    fn repelling_groups_do_not_weaken_alignment() {
+        let mut boid_settings = BoidSettings::new(2.0, 5.0, 0.0, 0.0, 1.0, 20, 20);
+        let mut grid = super::super::populate(0, 1, &boid_settings);
+        boid_settings
+            .set_group_count(2, &mut grid)
+            .set_affinity(0, 1, -1.0);
+        let boid = super::super::Boid::new(Vector2::new(10.0, 10.0), Vector2::new(1.0, 0.0), 0);
+        let mate = super::super::Boid::new(Vector2::new(13.0, 10.0), Vector2::new(0.0, 1.0), 0);
+        let other = super::super::Boid::new(Vector2::new(10.0, 13.0), Vector2::new(0.0, -1.0), 1);
+
+        let mut neighbourhood = Neighbourhood::new(&boid, &boid_settings);
+        neighbourhood.add(&mate, None, &boid_settings);
+        neighbourhood.add(&other, None, &boid_settings);
+        let force = neighbourhood.force(&boid_settings);
+        assert!((force - Vector2::new(0.0, 1.0)).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn only_applied_mouse_tool_disturbs() {
+        let mut boid_settings = settings(BorderSettings::Bounded);
+        boid_settings
+            .set_perching(true, 0.2, 100.0, 0, 10.0)
+            .set_mouse_position(10.0, 10.0);
+        let mut grid = super::super::grid_init(1, &boid_settings);
+        let position = Vector2::new(12.0, 10.0);
+        let (grid_row, grid_column) = get_grid_position(position, &boid_settings, &grid);
+        grid.add_val(
+            super::super::Boid::new(position, Vector2::ZERO, 0),
+            grid_row,
+            grid_column,
+        );
+        assert!(!disturbed(0, &grid, &boid_settings));
+        boid_settings.set_mouse_hovering(true);
+        assert!(disturbed(0, &grid, &boid_settings));
+    }
+
+    /// Returns the variance of the velocity the noise of the `noise_model`
+    /// adds to boids heading right, across 200 units of time.
+    fn noise_spread(noise_model: NoiseModel) -> f32 {
+        let mut boid_settings = settings(BorderSettings::Bounded);
+        boid_settings.set_noise(1.0).set_noise_model(noise_model);
+        let delta = 0.2;
+        let mut spread = 0.0;
+        let boids = 2000;
+        for _ in 0..boids {
+            let mut boid = super::super::Boid::new(Vector2::ZERO, Vector2::new(1.0, 0.0), 0);
+            let mut velocity = Vector2::ZERO;
+            for _ in 0..1000 {
+                advance_noise(&mut boid, &boid_settings, delta);
+                velocity += noise_force(&boid, &boid_settings, delta) * delta;
+            }
+            spread += velocity.sqr_magnitude();
+        }
+        spread / boids as f32
+    }
+
+    #[test]
+    fn noise_models_spread_comparably() {
+        let white = noise_spread(NoiseModel::White);
+        for noise_model in [
+            NoiseModel::Wander,
+            NoiseModel::OrnsteinUhlenbeck,
+            NoiseModel::Levy,
+        ] {
+            let ratio = noise_spread(noise_model) / white;
+            assert!((0.6..1.6).contains(&ratio), "{noise_model:?}: {ratio}");
+        }
+    }
+
+    /// Returns the force of the rules on a boid heading right from a single
+    /// neighbour at the given horizontal `distance`.
+    fn neighbour_force(distance: f32, boid_settings: &BoidSettings) -> Vector2 {
+        let boid = super::super::Boid::new(Vector2::new(5.0, 10.0), Vector2::new(1.0, 0.0), 0);
+        let other = super::super::Boid::new(Vector2::new(5.0 + distance, 10.0), Vector2::ZERO, 0);
+        let mut neighbourhood = Neighbourhood::new(&boid, boid_settings);
+        neighbourhood.add(&other, None, boid_settings);
+        neighbourhood.force(boid_settings)
+    }
+
+    #[test]
+    fn kernels_weigh_by_distance() {
+        let sqr_range = 4.0;
+        for kernel in Kernel::OPTIONS {
+            assert!(kernel.weight(0.0, sqr_range).is_finite(), "{kernel:?}");
+            assert!(
+                kernel.weight(0.25, sqr_range) >= kernel.weight(3.0, sqr_range),
+                "{kernel:?}"
+            );
+        }
+        assert_eq!(Kernel::Uniform.weight(3.9, sqr_range), 1.0);
+        assert_eq!(Kernel::InverseDistance.weight(1.0, sqr_range), 2.0);
+        assert_eq!(Kernel::InverseSquare.weight(1.0, sqr_range), 4.0);
+        for kernel in [Kernel::Linear, Kernel::Smoothstep] {
+            assert_eq!(kernel.weight(0.0, sqr_range), 1.0);
+            assert_eq!(kernel.weight(sqr_range, sqr_range), 0.0);
+        }
+    }
+
+    #[test]
+    fn inverse_kernels_do_not_strengthen_cohesion() {
+        let mut boid_settings = settings(BorderSettings::None);
+        let uniform = neighbour_force(2.5, &boid_settings);
+        boid_settings.set_kernels(
+            Kernel::Uniform,
+            Kernel::InverseSquare,
+            Kernel::InverseSquare,
+        );
+        let inverse = neighbour_force(2.5, &boid_settings);
+        assert!((inverse - uniform).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn separation_blends_into_cohesion() {
+        let boid_settings = settings(BorderSettings::None);
+        let protected_range = 2.0;
+        assert_eq!(separation_share(0.0, 4.0), 1.0);
+        assert_eq!(separation_share(4.0, 4.0), 0.0);
+        let inside = neighbour_force(protected_range - 0.001, &boid_settings);
+        let outside = neighbour_force(protected_range + 0.001, &boid_settings);
+        assert!((inside - outside).magnitude() < 1e-3);
+        // Well within the protected range, the neighbour is only avoided.
+        assert!(neighbour_force(1.0, &boid_settings).x < 0.0);
+    }
+
+    #[test]
+    fn reservoir_samples_uniformly() {
+        let mut small = Reservoir::new(10);
+        (0..5).for_each(|value| small.offer(value));
+        assert_eq!(small.samples(), vec![0, 1, 2, 3, 4]);
+
+        let mut counts = [0; 20];
+        for _ in 0..10000 {
+            let mut reservoir = Reservoir::new(5);
+            (0..20).for_each(|value| reservoir.offer(value));
+            let samples = reservoir.samples();
+            assert_eq!(samples.len(), 5);
+            samples.into_iter().for_each(|value| counts[value] += 1);
+        }
+        // Every value is expected to be kept a quarter of the time.
+        for count in counts {
+            assert!((2200..2800).contains(&count), "{counts:?}");
+        }
+    }
+
+    #[test]
+    fn wrapped_diff_takes_shortest_way() {
+        let position = Vector2::new(1.0, 1.0);
+        let other = Vector2::new(19.0, 10.0);
+        let diff = |border_settings| wrapped_diff(position, other, &settings(border_settings));
+        assert_eq!(diff(BorderSettings::Wrapping), Vector2::new(-2.0, 9.0));
+        assert_eq!(diff(BorderSettings::Bounded), Vector2::new(18.0, 9.0));
+        assert_eq!(
+            diff(BorderSettings::BoundedVertical),
+            Vector2::new(-2.0, 9.0)
+        );
+        let far = wrapped_diff(
+            position,
+            Vector2::new(10.0, 19.0),
+            &settings(BorderSettings::BoundedHorizontal),
+        );
+        assert_eq!(far, Vector2::new(9.0, -2.0));
+    }
+}
@@ -0,0 +1,264 @@
+//! Coarse occupancy layer of walls painted into the world.
+//!
+//! # Walls
+//!
+//! Contains the [`WallLayer`], which divides the world into square cells that
+//! are either free or occupied by a wall. Walls can be painted and erased at
+//! runtime, and are used by the simulation to repel boids and to prevent them
+//! from passing through.
+
+use crate::vector2::Vector2;
+
+/// The width and height of a single wall cell in world units.
+pub const WALL_CELL_SIZE: f32 = 1.0;
+
+/// A grid of cells, each either occupied by a wall or free.
+pub struct WallLayer {
+    /// Row major occupancy of each cell.
+    cells: Vec<bool>,
+    /// The amount of occupied cells.
+    count: usize,
+    /// The amount of columns in the layer.
+    pub columns: usize,
+    /// The amount of rows in the layer.
+    pub rows: usize,
+}
+
+impl WallLayer {
+    /// Creates a new empty [`WallLayer`] covering a world of the given `width`
+    /// and `height`.
+    pub fn new(width: usize, height: usize) -> WallLayer {
+        let columns = (width as f32 / WALL_CELL_SIZE).ceil() as usize;
+        let rows = (height as f32 / WALL_CELL_SIZE).ceil() as usize;
+        WallLayer {
+            cells: vec![false; columns * rows],
+            count: 0,
+            columns,
+            rows,
+        }
+    }
+
+    /// Resizes the layer to cover a world of the given `width` and `height`,
+    /// keeping the walls that still fall within the new size.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let mut new_layer = WallLayer::new(width, height);
+        for row in 0..self.rows.min(new_layer.rows) {
+            for column in 0..self.columns.min(new_layer.columns) {
+                if self.cells[column + row * self.columns] {
+                    new_layer.cells[column + row * new_layer.columns] = true;
+                    new_layer.count += 1;
+                }
+            }
+        }
+        *self = new_layer;
+    }
+
+    /// Whether there are no walls in the layer.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Returns the row and column of the cell containing the `position`.
+    #[inline]
+    pub fn cell_of(&self, position: Vector2) -> (i32, i32) {
+        (
+            (position.y / WALL_CELL_SIZE).floor() as i32,
+            (position.x / WALL_CELL_SIZE).floor() as i32,
+        )
+    }
+
+    /// Returns the center of the cell at `row` and `column` in world coordinates.
+    #[inline]
+    pub fn cell_center(row: i32, column: i32) -> Vector2 {
+        Vector2::new(
+            (column as f32 + 0.5) * WALL_CELL_SIZE,
+            (row as f32 + 0.5) * WALL_CELL_SIZE,
+        )
+    }
+
+    /// Whether the cell at `row` and `column` is a wall. Cells outside of the
+    /// layer are never walls.
+    #[inline]
+    pub fn is_wall_cell(&self, row: i32, column: i32) -> bool {
+        row >= 0
+            && (row as usize) < self.rows
+            && column >= 0
+            && (column as usize) < self.columns
+            && self.cells[column as usize + row as usize * self.columns]
+    }
+
+    /// Whether the `position` falls within a wall.
+    #[inline]
+    pub fn is_wall(&self, position: Vector2) -> bool {
+        let (row, column) = self.cell_of(position);
+        self.is_wall_cell(row, column)
+    }
+
+    /// Sets every cell with its center within `radius` of `position` to `wall`.
+    fn brush(&mut self, position: Vector2, radius: f32, wall: bool) {
+        let (row, column) = self.cell_of(position);
+        let reach = (radius / WALL_CELL_SIZE).ceil() as i32;
+        for r in (row - reach)..=(row + reach) {
+            for c in (column - reach)..=(column + reach) {
+                if r < 0 || r as usize >= self.rows || c < 0 || c as usize >= self.columns {
+                    continue;
+                }
+                if (WallLayer::cell_center(r, c) - position).sqr_magnitude() > radius * radius {
+                    continue;
+                }
+                let cell = &mut self.cells[c as usize + r as usize * self.columns];
+                if *cell != wall {
+                    *cell = wall;
+                    if wall {
+                        self.count += 1;
+                    } else {
+                        self.count -= 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Paints walls in every cell within `radius` of `position`.
+    pub fn paint(&mut self, position: Vector2, radius: f32) {
+        self.brush(position, radius, true);
+    }
+
+    /// Erases walls in every cell within `radius` of `position`.
+    pub fn erase(&mut self, position: Vector2, radius: f32) {
+        self.brush(position, radius, false);
+    }
+
+    /// Removes all walls from the layer.
+    pub fn clear(&mut self) {
+        self.cells.fill(false);
+        self.count = 0;
+    }
+
+    /// Returns the `position` moved to the center of the nearest free cell
+    /// within the layer if it lies within a wall, such as when a wall is
+    /// painted over a boid, or the `position` itself otherwise. Positions
+    /// within a layer without free cells are not moved.
+    pub fn push_out(&self, position: Vector2) -> Vector2 {
+        if !self.is_wall(position) {
+            return position;
+        }
+        let (row, column) = self.cell_of(position);
+        let within = |r: i32, c: i32| {
+            r >= 0 && (r as usize) < self.rows && c >= 0 && (c as usize) < self.columns
+        };
+        // Search rings of cells around the wall until a free cell is found.
+        for ring in 1..=self.rows.max(self.columns) as i32 {
+            let mut nearest: Option<Vector2> = None;
+            for r in (row - ring)..=(row + ring) {
+                for c in (column - ring)..=(column + ring) {
+                    let on_ring = (r - row).abs() == ring || (c - column).abs() == ring;
+                    if !on_ring || !within(r, c) || self.is_wall_cell(r, c) {
+                        continue;
+                    }
+                    let center = WallLayer::cell_center(r, c);
+                    if nearest.is_none_or(|nearest| {
+                        (center - position).sqr_magnitude() < (nearest - position).sqr_magnitude()
+                    }) {
+                        nearest = Some(center);
+                    }
+                }
+            }
+            if let Some(nearest) = nearest {
+                return nearest;
+            }
+        }
+        position
+    }
+
+    /// Moves from `from` towards `to` in steps smaller than a cell, returning
+    /// the last free position before the first wall that is hit, along with
+    /// whether the wall was entered horizontally and/or vertically.
+    /// Returns `None` if no wall is hit, or if `from` already lies within a
+    /// wall, in which case it must first be moved out, see [`WallLayer::push_out`].
+    pub fn first_hit(&self, from: Vector2, to: Vector2) -> Option<(Vector2, bool, bool)> {
+        if self.is_wall(from) {
+            return None;
+        }
+        let diff = to - from;
+        let steps = (diff.magnitude() / (WALL_CELL_SIZE * 0.5)).ceil().max(1.0) as usize;
+        let mut last = from;
+        let mut last_cell = self.cell_of(from);
+        for step in 1..=steps {
+            let current = from + diff * (step as f32 / steps as f32);
+            let cell = self.cell_of(current);
+            if self.is_wall_cell(cell.0, cell.1) {
+                let mut horizontal = cell.1 != last_cell.1;
+                let mut vertical = cell.0 != last_cell.0;
+                // Entered diagonally, only bounce along the blocked axes.
+                if horizontal && vertical {
+                    horizontal = self.is_wall_cell(last_cell.0, cell.1);
+                    vertical = self.is_wall_cell(cell.0, last_cell.1);
+                    if !horizontal && !vertical {
+                        horizontal = true;
+                        vertical = true;
+                    }
+                }
+                return Some((last, horizontal, vertical));
+            }
+            last = current;
+            last_cell = cell;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A layer of 10 by 10 cells with a vertical wall in column 5.
+    fn layer_with_wall() -> WallLayer {
+        let mut walls = WallLayer::new(10, 10);
+        for row in 0..10 {
+            walls.paint(WallLayer::cell_center(row, 5), 0.1);
+        }
+        walls
+    }
+
+    #[test]
+    fn first_hit_stops_before_wall() {
+        let walls = layer_with_wall();
+        let hit = walls.first_hit(Vector2::new(2.5, 2.5), Vector2::new(8.5, 2.5));
+        let (free_position, horizontal, vertical) = hit.expect("path crosses the wall");
+        assert!(free_position.x < 5.0);
+        assert!(!walls.is_wall(free_position));
+        assert!(horizontal && !vertical);
+    }
+
+    #[test]
+    fn first_hit_misses_free_path() {
+        let walls = layer_with_wall();
+        assert!(
+            walls
+                .first_hit(Vector2::new(1.5, 1.5), Vector2::new(3.5, 8.5))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn first_hit_ignores_start_within_wall() {
+        let walls = layer_with_wall();
+        assert!(
+            walls
+                .first_hit(Vector2::new(5.5, 2.5), Vector2::new(8.5, 2.5))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn push_out_moves_to_nearest_free_cell() {
+        let walls = layer_with_wall();
+        let free = Vector2::new(2.5, 2.5);
+        assert_eq!(walls.push_out(free), free);
+        let pushed = walls.push_out(Vector2::new(5.2, 3.5));
+        assert!(!walls.is_wall(pushed));
+        assert!((pushed - Vector2::new(4.5, 3.5)).magnitude() < 1e-5);
+    }
+}
@@ -20,12 +20,18 @@
 //! values in a given cell.
 //! [`Iter<T>`] can be created using the [`Grid::iter_all`] method, which returns
 //! an iterator over all values in the grid, independent of which cell these values are in.
+//!
+//! ## Wrapping
+//! The rows and columns of the grid can wrap around, see [`Grid::set_wrapping`],
+//! in which case positions beyond the first or last row or column refer to the
+//! cells on the opposite side of the grid, forming a torus.
 
 // Index given to any non-existing value, similar to c's NULL.
 const EMPTY: i32 = -1;
 
 /// Stores the value in the [`Grid<T>`], along with the index of the next value in the
 /// list of all values.
+#[derive(Clone)]
 pub struct ValueNode<T> {
     pub next_index: i32,
     pub val: T,
@@ -44,6 +50,7 @@ pub struct GridNode {
 /// Defines the custom grid-like linked list datastructure.
 /// The values are stored in `values`, while the starting indices
 /// of the linked lists are located in the `grid` field.
+#[derive(Clone)]
 pub struct Grid<T> {
     /// The values in the grid, contained in [`ValueNode`]s
     /// to link to the next value in a cell.
@@ -56,6 +63,10 @@ pub struct Grid<T> {
     pub rows: usize,
     /// The amount of columns in the grid.
     pub columns: usize,
+    /// Whether the rows beyond the first and last row wrap around.
+    pub wrap_rows: bool,
+    /// Whether the columns beyond the first and last column wrap around.
+    pub wrap_columns: bool,
 }
 
 impl<'a, T> Grid<T> {
@@ -78,9 +89,19 @@ impl<'a, T> Grid<T> {
             count: 0,
             columns,
             rows,
+            wrap_rows: false,
+            wrap_columns: false,
         }
     }
 
+    /// Sets whether the rows and columns of the grid wrap around, given by
+    /// `wrap_rows` and `wrap_columns`.
+    pub fn set_wrapping(&mut self, wrap_rows: bool, wrap_columns: bool) -> &mut Self {
+        self.wrap_rows = wrap_rows;
+        self.wrap_columns = wrap_columns;
+        self
+    }
+
     /// Returns the iterator over all of the values in the [`Grid<T>`].
     pub fn iter_all(&'a self) -> Iter<'a, T> {
         Iter::new(self)
@@ -111,9 +132,20 @@ impl<'a, T> Grid<T> {
     }
 
     /// Returns the index of the cell given by `row` and `column`, or
-    /// [`Grid::EMPTY`] if the position falls outside of the grid.
+    /// [`Grid::EMPTY`] if the position falls outside of the grid. Rows and
+    /// columns that wrap around are first wrapped into the grid.
     #[inline]
     pub fn index_from_pos(&self, row: i32, column: i32) -> i32 {
+        let row = if self.wrap_rows {
+            row.rem_euclid(self.rows as i32)
+        } else {
+            row
+        };
+        let column = if self.wrap_columns {
+            column.rem_euclid(self.columns as i32)
+        } else {
+            column
+        };
         if row >= 0 && (row as usize) < self.rows && column >= 0 && (column as usize) < self.columns
         {
             column + row * self.columns as i32
@@ -147,6 +179,79 @@ impl<'a, T> Grid<T> {
         }
     }
 
+    /// Returns the index of the value linked before the value with the given
+    /// `index` in the cell given by `row` and `column`, or [`Grid::EMPTY`] if
+    /// it is the first value in the cell.
+    pub fn find_prev_index(&self, index: usize, row: i32, column: i32) -> i32 {
+        let mut prev_index = Self::EMPTY;
+        for value_index in self.iter_from_index(self.index_from_pos(row, column)) {
+            if value_index == index {
+                break;
+            }
+            prev_index = value_index as i32;
+        }
+        prev_index
+    }
+
+    /// Returns the indices of the `k` values closest to a position within the
+    /// cell given by `row` and `column`, along with their squared distance,
+    /// sorted from nearest to furthest.
+    ///
+    /// The cells are searched in square rings around the given cell, up to
+    /// `max_ring` cells away, where `cell_extent` is the smallest side of a
+    /// cell. The squared distance to each value is given by `sqr_distance`,
+    /// which skips the value when returning `None`. When the grid wraps
+    /// around, rings larger than the grid visit cells more than once, where
+    /// values already among the nearest are skipped.
+    pub fn k_nearest(
+        &self,
+        row: i32,
+        column: i32,
+        k: usize,
+        max_ring: i32,
+        cell_extent: f32,
+        mut sqr_distance: impl FnMut(usize) -> Option<f32>,
+    ) -> Vec<(usize, f32)> {
+        let mut nearest: Vec<(usize, f32)> = Vec::with_capacity(k + 1);
+        if k == 0 {
+            return nearest;
+        }
+        let wrapping = self.wrap_rows || self.wrap_columns;
+        for ring in 0..=max_ring {
+            for r_offset in -ring..=ring {
+                // Only the border of the ring, as the inside has already been searched.
+                let step = if r_offset.abs() == ring {
+                    1
+                } else {
+                    (ring * 2).max(1) as usize
+                };
+                for c_offset in (-ring..=ring).step_by(step) {
+                    let cell_index = self.index_from_pos(row + r_offset, column + c_offset);
+                    for value_index in self.iter_from_index(cell_index) {
+                        if wrapping && nearest.iter().any(|&(index, _)| index == value_index) {
+                            continue;
+                        }
+                        let Some(distance) = sqr_distance(value_index) else {
+                            continue;
+                        };
+                        if nearest.len() == k && distance >= nearest[k - 1].1 {
+                            continue;
+                        }
+                        let position = nearest.partition_point(|&(_, other)| other <= distance);
+                        nearest.insert(position, (value_index, distance));
+                        nearest.truncate(k);
+                    }
+                }
+            }
+            // Values in further rings are at least `ring` cells away.
+            let bound = ring as f32 * cell_extent;
+            if nearest.len() == k && nearest[k - 1].1 <= bound * bound {
+                break;
+            }
+        }
+        nearest
+    }
+
     /// Add a new `val` to the grid at a cell given by `row` and `column`.
     /// If the given location does not fall in the grid, the value is only
     /// added to the values vec and not to any cell.
@@ -220,6 +325,39 @@ impl<'a, T> Grid<T> {
             grid_node.count += 1;
         }
     }
+
+    /// Removes and returns the value with the given `index`, which is linked in
+    /// the cell given by `row` and `column`. To keep the values contiguous, the
+    /// last value takes the place of the removed value, requiring the cell
+    /// given by `last_row` and `last_column` of the last value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `index` falls outside of the values vec.
+    pub fn remove_val(
+        &mut self,
+        index: usize,
+        (row, column): (i32, i32),
+        (last_row, last_column): (i32, i32),
+    ) -> T {
+        let prev_index = self.find_prev_index(index, row, column);
+        self.unlink_val(index, prev_index, row, column);
+
+        let last_index = self.count - 1;
+        if last_index != index {
+            let prev_index = self.find_prev_index(last_index, last_row, last_column);
+            self.unlink_val(last_index, prev_index, last_row, last_column);
+        }
+
+        let removed = self.values.swap_remove(index);
+        self.count -= 1;
+
+        // Link the moved value at its new index.
+        if last_index != index {
+            self.link_val(index, last_row, last_column);
+        }
+        removed.val
+    }
 }
 
 /// Iterator over the indices of values in given cell in the [`Grid<T>`].
@@ -277,3 +415,79 @@ impl<'a, T> Iterator for Iter<'a, T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector2::Vector2;
+
+    /// A grid of 10 by 10 cells of a single unit, holding the given `positions`.
+    fn grid_of(positions: &[Vector2]) -> Grid<Vector2> {
+        let mut grid = Grid::new(positions.len(), 10, 10);
+        for &position in positions {
+            grid.add_val(position, position.y as i32, position.x as i32);
+        }
+        grid
+    }
+
+    #[test]
+    fn k_nearest_finds_closest_values() {
+        let positions: Vec<Vector2> = (0..40)
+            .map(|i| Vector2::new((i * 7 % 10) as f32 + 0.5, (i * 3 % 10) as f32 + 0.3))
+            .collect();
+        let grid = grid_of(&positions);
+        let center = Vector2::new(4.2, 5.6);
+        let nearest = grid.k_nearest(5, 4, 6, 20, 1.0, |index| {
+            Some((grid.values[index].val - center).sqr_magnitude())
+        });
+
+        let mut expected: Vec<f32> = positions
+            .iter()
+            .map(|&position| (position - center).sqr_magnitude())
+            .collect();
+        expected.sort_by(f32::total_cmp);
+        let distances: Vec<f32> = nearest.iter().map(|&(_, distance)| distance).collect();
+        assert_eq!(distances, expected[..6]);
+    }
+
+    #[test]
+    fn k_nearest_skips_values() {
+        let grid = grid_of(&[Vector2::new(2.5, 2.5), Vector2::new(3.5, 2.5)]);
+        let nearest = grid.k_nearest(2, 2, 3, 20, 1.0, |index| (index != 0).then_some(1.0));
+        assert_eq!(nearest, vec![(1, 1.0)]);
+    }
+
+    #[test]
+    fn index_from_pos_wraps_around() {
+        let mut grid: Grid<Vector2> = Grid::new(0, 4, 3);
+        assert_eq!(grid.index_from_pos(-1, 0), Grid::<Vector2>::EMPTY);
+        assert_eq!(grid.index_from_pos(1, 4), Grid::<Vector2>::EMPTY);
+
+        grid.set_wrapping(true, false);
+        assert_eq!(grid.index_from_pos(-1, 0), 8);
+        assert_eq!(grid.index_from_pos(4, 2), 6);
+        assert_eq!(grid.index_from_pos(1, 4), Grid::<Vector2>::EMPTY);
+
+        grid.set_wrapping(false, true);
+        assert_eq!(grid.index_from_pos(1, 4), 4);
+        assert_eq!(grid.index_from_pos(0, -1), 3);
+        assert_eq!(grid.index_from_pos(-1, 0), Grid::<Vector2>::EMPTY);
+    }
+
+    #[test]
+    fn k_nearest_wraps_without_repeats() {
+        let mut grid = grid_of(&[
+            Vector2::new(9.5, 5.5),
+            Vector2::new(3.5, 5.5),
+            Vector2::new(5.5, 5.5),
+        ]);
+        grid.set_wrapping(true, true);
+        // Distances measured the shortest way around the columns.
+        let center = Vector2::new(0.5, 5.5);
+        let nearest = grid.k_nearest(5, 0, 3, 20, 1.0, |index| {
+            let diff = grid.values[index].val.x - center.x;
+            Some((diff - 10.0 * (diff / 10.0).round()).powi(2))
+        });
+        assert_eq!(nearest, vec![(0, 1.0), (1, 9.0), (2, 25.0)]);
+    }
+}
@@ -29,23 +29,41 @@ use std::{
     time::{Duration, Instant},
 };
 
+mod args;
 mod boids;
 mod grid;
 mod menu;
 mod menu_handling;
 mod render;
 mod vector2;
+mod vector3;
 
 use crate::{
-    boids::{Boid, BoidSettings, BorderSettings, populate, update_boids},
+    args::Args,
+    boids::{
+        Attractor, Boid, BoidSettings, BorderSettings, Environment, Flock3D, FoodSource,
+        Integrator, Kernel, MouseTool, NeighbourMode, NeighbourSampling, NoiseModel, Obstacle,
+        Path, UpdateMode,
+        affinity::AffinityMatrix,
+        clock::Clock,
+        compare_update_modes,
+        flow::FlowGenerator,
+        formation::{Bitmap, Formation},
+        populate, remove_boids_within, spawn_boid, update_boids,
+    },
     menu::Menu,
-    menu_handling::setup_menu,
+    menu_handling::{MenuState, setup_menu},
 };
 use crate::{grid::Grid, menu_handling::on_menu_change};
-use crate::{menu::draw_menu, render::draw_boids};
+use crate::{
+    menu::draw_menu,
+    render::{Camera, draw_boids, draw_boids_3d, draw_status, draw_tool_indicator},
+    vector2::Vector2,
+};
 
 // Simulation settings
 const COUNT: usize = 5000;
+const COUNT_3D: usize = 1500;
 const GROUP_COUNT: u8 = 1;
 const FRAME_TIME: Duration = Duration::from_millis(20);
 
@@ -55,22 +73,102 @@ pub const COHESION_DIST: f32 = 5f32;
 pub const COHESION_FORCE: f32 = 0.01f32;
 pub const SEPARATION_FORCE: f32 = 0.05f32;
 pub const ALIGNMENT_FORCE: f32 = 0.05f32;
+pub const SEPARATION_KERNEL: Kernel = Kernel::Uniform;
+pub const COHESION_KERNEL: Kernel = Kernel::Uniform;
+pub const ALIGNMENT_KERNEL: Kernel = Kernel::Uniform;
 pub const MIN_SPEED: f32 = 2.0;
 pub const TURN_FORCE: f32 = 1.5;
 pub const MARGIN: f32 = 20.0;
 pub const GRAVITY: f32 = 0.08;
 pub const NOISE_FORCE: f32 = 0.05;
+pub const NOISE_MODEL: NoiseModel = NoiseModel::White;
 pub const FRICTION_COEFFICIENT: f32 = 0.01;
 pub const SQUARED_FRICTION: bool = true;
-pub const MOUSE_RANGE: f32 = 20.0;
-pub const MOUSE_FORCE: f32 = 5.0;
-pub const MOUSE_RANGE_DOWN: f32 = 10.0;
-pub const MOUSE_FORCE_DOWN: f32 = -5.0;
+pub const MOUSE_TOOL: MouseTool = MouseTool::Hover;
+pub const HOVER_FORCE: f32 = 5.0;
+pub const HOVER_RANGE: f32 = 20.0;
+pub const ATTRACT_FORCE: f32 = 5.0;
+pub const ATTRACT_RANGE: f32 = 20.0;
+pub const REPEL_FORCE: f32 = 5.0;
+pub const REPEL_RANGE: f32 = 10.0;
+pub const VORTEX_FORCE: f32 = 5.0;
+pub const VORTEX_RANGE: f32 = 20.0;
+pub const WIND_FORCE: f32 = 5.0;
+pub const WIND_RANGE: f32 = 15.0;
+pub const SPAWN_COUNT: usize = 5;
+pub const SPAWN_RANGE: f32 = 2.0;
+pub const DELETE_COUNT: usize = 20;
+pub const DELETE_RANGE: f32 = 3.0;
+pub const PREDATOR_COUNT: usize = 0;
+pub const CHASE_FORCE: f32 = 2.0;
+pub const FLEE_FORCE: f32 = 3.0;
+pub const PREDATOR_SPEED: f32 = 12.0;
+pub const VIEW_ANGLE: f32 = 360.0;
+pub const VIEW_FALLOFF: bool = false;
+pub const NEIGHBOUR_MODE: NeighbourMode = NeighbourMode::Metric;
+pub const K_NEAREST: usize = 7;
+pub const NEIGHBOUR_SAMPLING: NeighbourSampling = NeighbourSampling::Exact;
+pub const SAMPLE_BUDGET: usize = 300;
+pub const UPDATE_MODE: UpdateMode = UpdateMode::Sequential;
+pub const INTEGRATOR: Integrator = Integrator::SemiImplicitEuler;
+pub const FIXED_TIMESTEP: bool = true;
+/// The real time in seconds covered by a single update with a fixed timestep.
+pub const FIXED_STEP: f32 = 1.0 / 60.0;
+/// The most updates per frame, preventing slow frames from piling up updates.
+pub const MAX_STEPS_PER_FRAME: u32 = 8;
+pub const AVOID_FORCE: f32 = 3.0;
+pub const LOOK_AHEAD: f32 = 8.0;
+pub const ROCK_RADIUS: f32 = 6.0;
+pub const WALL_FORCE: f32 = 2.0;
+pub const WALL_BRUSH_RADIUS: f32 = 1.5;
+pub const PATH_FORCE: f32 = 1.0;
+pub const WAYPOINT_RADIUS: f32 = 5.0;
+pub const FORMATION_FORCE: f32 = 1.0;
+pub const FLOW_GENERATOR: FlowGenerator = FlowGenerator::None;
+pub const FLOW_STRENGTH: f32 = 0.5;
+pub const WIND_DIRECTION: f32 = 0.0;
+pub const SHOW_FLOW: bool = false;
+pub const ECOSYSTEM: bool = false;
+pub const ENERGY_DECAY: f32 = 0.004;
+pub const FEEDING_RATE: f32 = 0.1;
+pub const GRAZING_RATE: f32 = 0.005;
+pub const REPRODUCTION_ENERGY: f32 = 2.0;
+pub const MAX_AGE: f32 = 1500.0;
+pub const CRAB_MODE: bool = false;
+pub const HOP_RATE: f32 = 0.02;
+pub const HOP_SPEED: f32 = 8.0;
+pub const SCATTER_FORCE: f32 = 30.0;
+pub const SCATTER_RANGE: f32 = 15.0;
+pub const PERCHING: bool = false;
+pub const PERCH_RATE: f32 = 0.2;
+pub const PERCH_TIME: f32 = 100.0;
+pub const TAKEOFF_COUNT: usize = 5;
+pub const DISTURB_RANGE: f32 = 10.0;
+pub const ATTRACTOR_STRENGTH: f32 = 3.0;
+pub const ATTRACTOR_RADIUS: f32 = 30.0;
+pub const ATTRACTOR_DEPLETION: f32 = 0.0;
+pub const FOOD_RADIUS: f32 = 8.0;
+
+// Camera settings
+/// The initial distance of the camera, relative to the size of the volume.
+pub const CAMERA_DISTANCE: f32 = 2.5;
+pub const CAMERA_ORBITING: bool = true;
+/// The rotation in radians per key press.
+pub const CAMERA_STEP: f32 = 0.1;
+/// The change in distance per key press.
+pub const ZOOM_STEP: f32 = 1.1;
+
+/// The simulated time per second of real time, which the boid parameters are
+/// tuned for.
+// TODO: remove the need for this timescale by using sane parameters.
+const TIME_SCALE: f32 = 10.0;
 
 struct SimData {
     sim_settings: Box<SimulationSettings>,
     boid_settings: Box<BoidSettings>,
     boid_data: Box<Grid<Boid>>,
+    flock_3d: Box<Flock3D>,
+    environment: Box<Environment>,
     menu: Box<Menu<menu_handling::MenuID>>,
 }
 
@@ -79,12 +177,16 @@ impl SimData {
         sim_settings: SimulationSettings,
         boid_settings: BoidSettings,
         boid_data: Grid<Boid>,
+        flock_3d: Flock3D,
+        environment: Environment,
         menu: Menu<menu_handling::MenuID>,
     ) -> Self {
         Self {
             sim_settings: Box::new(sim_settings),
             boid_settings: Box::new(boid_settings),
             boid_data: Box::new(boid_data),
+            flock_3d: Box::new(flock_3d),
+            environment: Box::new(environment),
             menu: Box::new(menu),
         }
     }
@@ -103,6 +205,38 @@ struct SimulationSettings {
     /// Whether the menu is visible.
     menu_visible: bool,
 
+    /// The selections made in the menu.
+    menu_state: MenuState,
+
+    /// Whether the mouse paints walls instead of affecting the boids.
+    drawing_walls: bool,
+
+    /// The start of the line obstacle currently being placed, if any.
+    line_start: Option<Vector2>,
+
+    /// The message shown at the bottom of the screen, if any.
+    status: Option<String>,
+
+    /// Whether the 3D flock is simulated and shown instead of the regular boids.
+    three_d: bool,
+
+    /// The viewpoint from which the 3D flock is shown.
+    camera: Camera,
+
+    /// The clock shown by the boids, if running.
+    clock: Option<Clock>,
+
+    /// The offset from UTC in hours of the time shown by the clock, or `None`
+    /// to show the local time.
+    utc_offset: Option<f32>,
+
+    /// The affinity matrix loaded at the start, restored on every reset.
+    affinity: Option<AffinityMatrix>,
+
+    /// Whether the boids are updated in steps of [`FIXED_STEP`] instead of
+    /// the duration of the last frame.
+    fixed_timestep: bool,
+
     /// The target interval between frames, can be exceeded if the simulation is
     /// too intensive.
     frame_time: Duration,
@@ -115,11 +249,21 @@ impl SimulationSettings {
     // TODO: Replace with new() for configurable settings.
     /// Initialises a new [`SimulationSettings`] struct with the values
     /// required at the start of the simulation loop.
-    pub fn init() -> SimulationSettings {
+    pub fn init(utc_offset: Option<f32>, affinity: Option<AffinityMatrix>) -> SimulationSettings {
         SimulationSettings {
             paused: false,
             running: true,
             menu_visible: false,
+            menu_state: MenuState::default(),
+            drawing_walls: false,
+            line_start: None,
+            status: None,
+            three_d: false,
+            camera: Camera::new(CAMERA_DISTANCE, CAMERA_ORBITING),
+            clock: None,
+            utc_offset,
+            affinity,
+            fixed_timestep: FIXED_TIMESTEP,
             frame_time: FRAME_TIME,
             sim_color: Colors::new(White, Black),
         }
@@ -149,14 +293,50 @@ fn boid_settings_init() -> Result<BoidSettings> {
         height,
     );
     boid_settings
+        .set_kernels(SEPARATION_KERNEL, COHESION_KERNEL, ALIGNMENT_KERNEL)
         .set_gravity(GRAVITY)
         .set_min_speed(MIN_SPEED)
         .set_border(BorderSettings::Bounded)
         .set_margin(MARGIN)
         .set_turn_force(TURN_FORCE)
         .set_noise(NOISE_FORCE)
+        .set_noise_model(NOISE_MODEL)
         .set_friction(FRICTION_COEFFICIENT, SQUARED_FRICTION)
-        .set_mouse_force(MOUSE_FORCE, MOUSE_RANGE);
+        .set_mouse_tool(MOUSE_TOOL)
+        .set_tool_settings(MouseTool::Hover, HOVER_FORCE, HOVER_RANGE)
+        .set_tool_settings(MouseTool::Attract, ATTRACT_FORCE, ATTRACT_RANGE)
+        .set_tool_settings(MouseTool::Repel, REPEL_FORCE, REPEL_RANGE)
+        .set_tool_settings(MouseTool::Vortex, VORTEX_FORCE, VORTEX_RANGE)
+        .set_tool_settings(MouseTool::Wind, WIND_FORCE, WIND_RANGE)
+        .set_tool_count(MouseTool::Spawn, SPAWN_COUNT, SPAWN_RANGE)
+        .set_tool_count(MouseTool::Delete, DELETE_COUNT, DELETE_RANGE)
+        .set_predator_forces(CHASE_FORCE, FLEE_FORCE)
+        .set_predator_speed(PREDATOR_SPEED)
+        .set_view(VIEW_ANGLE, VIEW_FALLOFF)
+        .set_neighbour_mode(NEIGHBOUR_MODE, K_NEAREST)
+        .set_neighbour_sampling(NEIGHBOUR_SAMPLING, SAMPLE_BUDGET)
+        .set_update_mode(UPDATE_MODE)
+        .set_integrator(INTEGRATOR)
+        .set_ecosystem(
+            ECOSYSTEM,
+            ENERGY_DECAY,
+            FEEDING_RATE,
+            GRAZING_RATE,
+            REPRODUCTION_ENERGY,
+            MAX_AGE,
+        )
+        .set_crab_mode(CRAB_MODE, HOP_RATE, HOP_SPEED, SCATTER_FORCE, SCATTER_RANGE)
+        .set_perching(
+            PERCHING,
+            PERCH_RATE,
+            PERCH_TIME,
+            TAKEOFF_COUNT,
+            DISTURB_RANGE,
+        )
+        .set_obstacle_avoidance(AVOID_FORCE, LOOK_AHEAD)
+        .set_wall_force(WALL_FORCE)
+        .set_path_following(PATH_FORCE, WAYPOINT_RADIUS)
+        .set_formation_force(FORMATION_FORCE);
     Ok(boid_settings)
 }
 
@@ -186,11 +366,71 @@ fn pause(sim_settings: &mut SimulationSettings) -> Result<()> {
 
 fn reset_settings(sim_data: &mut SimData) -> Result<()> {
     *sim_data.boid_settings = boid_settings_init()?;
-    *sim_data.menu = setup_menu(&sim_data.boid_settings);
+    sim_data
+        .boid_settings
+        .set_group_count(GROUP_COUNT, &mut sim_data.boid_data)
+        .set_predator_count(PREDATOR_COUNT, &mut sim_data.boid_data);
+    if let Some(affinity) = &sim_data.sim_settings.affinity {
+        sim_data
+            .boid_settings
+            .set_affinity_matrix(affinity.clone(), &mut sim_data.boid_data);
+    }
+    sim_data.sim_settings.menu_state = MenuState::default();
+    *sim_data.menu = setup_menu(
+        &sim_data.boid_settings,
+        &sim_data.boid_data,
+        &sim_data.environment,
+        &sim_data.sim_settings.menu_state,
+    );
     Ok(())
 }
 
-/// Handles key related input `event`s.
+/// Recreates the menu to reflect the current settings, keeping the same item
+/// selected.
+fn refresh_menu(sim_data: &mut SimData) {
+    let selected = sim_data.menu.selected();
+    *sim_data.menu = setup_menu(
+        &sim_data.boid_settings,
+        &sim_data.boid_data,
+        &sim_data.environment,
+        &sim_data.sim_settings.menu_state,
+    );
+    sim_data.menu.select(selected);
+}
+
+/// Places an obstacle at the current mouse position, where the `code`
+/// determines the type of obstacle:
+/// - `b`: A circular rock.
+/// - `n`: A square block.
+/// - `l`: A line, starting at the first press and ending at the second.
+fn place_obstacle(code: KeyCode, sim_data: &mut SimData) {
+    let position = sim_data.boid_settings.mouse_position;
+    let obstacle = match code {
+        KeyCode::Char('b') => Obstacle::Circle {
+            center: position,
+            radius: ROCK_RADIUS,
+        },
+        KeyCode::Char('n') => Obstacle::Rectangle {
+            min: position + -ROCK_RADIUS,
+            max: position + ROCK_RADIUS,
+        },
+        KeyCode::Char('l') => match sim_data.sim_settings.line_start.take() {
+            Some(start) => Obstacle::Segment {
+                start,
+                end: position,
+            },
+            None => {
+                sim_data.sim_settings.line_start = Some(position);
+                return;
+            }
+        },
+        _ => return,
+    };
+    sim_data.environment.add_obstacle(obstacle);
+}
+
+/// Handles key related input `event`s. While the 3D flock is shown, only the
+/// keys shared by both simulations and those moving the camera are handled.
 ///
 /// # Errors
 ///
@@ -200,33 +440,249 @@ fn on_key_event(event: KeyEvent, sim_data: &mut SimData) -> Result<()> {
         KeyCode::Esc => quit(&mut sim_data.sim_settings),
         KeyCode::Char(' ') => pause(&mut sim_data.sim_settings)?,
         KeyCode::Char('q') => quit(&mut sim_data.sim_settings),
-        KeyCode::Char('c') => {
-            if event.modifiers.contains(KeyModifiers::CONTROL) {
-                quit(&mut sim_data.sim_settings);
-            }
+        KeyCode::Char('c') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+            quit(&mut sim_data.sim_settings);
         }
         KeyCode::Char('o') => {
             sim_data.sim_settings.menu_visible = !sim_data.sim_settings.menu_visible
         }
         KeyCode::Char('r') => reset_settings(sim_data)?,
+        KeyCode::Char('f') => toggle_fixed_timestep(&mut sim_data.sim_settings),
+        KeyCode::Char('3') => toggle_three_d(sim_data),
+        _ if sim_data.sim_settings.three_d => move_camera(event.code, &mut sim_data.sim_settings),
+        KeyCode::Char('b' | 'n' | 'l') => place_obstacle(event.code, sim_data),
+        KeyCode::Char('p') => {
+            let position = sim_data.boid_settings.mouse_position;
+            sim_data
+                .environment
+                .add_food(FoodSource::new(position, FOOD_RADIUS));
+        }
+        KeyCode::Char('x') => {
+            sim_data.environment.clear();
+            refresh_menu(sim_data);
+        }
+        KeyCode::Char('w') => toggle_wall_drawing(sim_data),
+        KeyCode::Char('t') => cycle_mouse_tool(sim_data),
+        KeyCode::Char('e') => check_update_modes(sim_data),
+        KeyCode::Char('g') => toggle_formation(sim_data),
+        KeyCode::Char('c') => toggle_clock(sim_data),
         _ => (),
     };
     Ok(())
 }
 
-/// Handles mouse related input `event`s.
-fn on_mouse_event(event: MouseEvent, boid_settings: &mut BoidSettings) {
-    match event.kind {
-        MouseEventKind::Down(MouseButton::Left) => {
-            boid_settings.set_mouse_force(MOUSE_FORCE_DOWN, MOUSE_RANGE_DOWN);
+/// Moves the camera of the 3D flock based on the pressed key `code`:
+/// - Arrow keys: Rotate the camera around the flock, unless the menu is
+///   visible.
+/// - `+` and `-`: Zoom in and out.
+/// - `a`: Start or stop orbiting around the flock.
+fn move_camera(code: KeyCode, sim_settings: &mut SimulationSettings) {
+    let camera = &mut sim_settings.camera;
+    match code {
+        KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down
+            if sim_settings.menu_visible => {}
+        KeyCode::Left => camera.rotate(-CAMERA_STEP, 0.0),
+        KeyCode::Right => camera.rotate(CAMERA_STEP, 0.0),
+        KeyCode::Up => camera.rotate(0.0, CAMERA_STEP),
+        KeyCode::Down => camera.rotate(0.0, -CAMERA_STEP),
+        KeyCode::Char('+' | '=') => camera.zoom(1.0 / ZOOM_STEP),
+        KeyCode::Char('-') => camera.zoom(ZOOM_STEP),
+        KeyCode::Char('a') => camera.orbiting = !camera.orbiting,
+        _ => (),
+    }
+}
+
+/// Compares the sequential and synchronous update modes on the current boids
+/// and shows the largest deviations in the status line.
+fn check_update_modes(sim_data: &mut SimData) {
+    let comparison = compare_update_modes(
+        &sim_data.boid_data,
+        &sim_data.boid_settings,
+        &sim_data.environment,
+        FIXED_STEP * TIME_SCALE,
+    );
+    sim_data.sim_settings.status = Some(format!(
+        "Sequential deviation: {:.4}, reordered deviation: {:.4}",
+        comparison.sequential, comparison.reordered
+    ));
+}
+
+/// Switches between updating the boids with a fixed timestep and with the
+/// duration of the last frame, showing the result in the status line.
+fn toggle_fixed_timestep(sim_settings: &mut SimulationSettings) {
+    sim_settings.fixed_timestep = !sim_settings.fixed_timestep;
+    sim_settings.status = Some(
+        if sim_settings.fixed_timestep {
+            "Fixed timestep"
+        } else {
+            "Variable timestep"
         }
-        MouseEventKind::Up(MouseButton::Left) => {
-            boid_settings.set_mouse_force(MOUSE_FORCE, MOUSE_RANGE);
+        .to_string(),
+    );
+}
+
+/// Releases the boids from their formation, or gathers them back into it if
+/// already released, showing the result in the status line.
+fn toggle_formation(sim_data: &mut SimData) {
+    if let Some(formation) = &mut sim_data.environment.formation {
+        formation.released = !formation.released;
+        sim_data.sim_settings.status = Some(
+            if formation.released {
+                "Formation released"
+            } else {
+                "Formation gathered"
+            }
+            .to_string(),
+        );
+    }
+}
+
+/// Starts the clock shown by the boids, or stops it if already running and
+/// restores the formation it replaced.
+fn toggle_clock(sim_data: &mut SimData) {
+    let sim_settings = &mut sim_data.sim_settings;
+    match sim_settings.clock.take() {
+        Some(clock) => {
+            sim_data.environment.set_formation(clock.stop());
+            sim_settings.status = Some("Clock stopped".to_string());
         }
-        _ => (),
+        None => {
+            let replaced = sim_data.environment.formation.take();
+            sim_settings.clock = Some(Clock::new(sim_settings.utc_offset, replaced));
+            sim_settings.status = Some("Clock started".to_string());
+        }
+    }
+}
+
+/// Switches between the 2D and 3D simulation. While in 3D, the mouse does not
+/// affect the boids.
+fn toggle_three_d(sim_data: &mut SimData) {
+    let sim_settings = &mut sim_data.sim_settings;
+    sim_settings.three_d = !sim_settings.three_d;
+    sim_data
+        .boid_settings
+        .set_mouse_active(false)
+        .set_mouse_hovering(false);
+}
+
+/// Switches the mouse between painting walls and affecting the boids. While
+/// painting walls, the mouse tool is not applied to the boids.
+fn toggle_wall_drawing(sim_data: &mut SimData) {
+    let sim_settings = &mut sim_data.sim_settings;
+    sim_settings.drawing_walls = !sim_settings.drawing_walls;
+    sim_data
+        .boid_settings
+        .set_mouse_active(false)
+        .set_mouse_hovering(false);
+}
+
+/// Switches to the next mouse tool.
+fn cycle_mouse_tool(sim_data: &mut SimData) {
+    let boid_settings = &mut sim_data.boid_settings;
+    boid_settings.set_mouse_tool(boid_settings.mouse_tool.next());
+    refresh_menu(sim_data);
+}
+
+/// Applies the `mouse_tool` at the `position`, if it spawns or deletes boids.
+/// The other tools are applied by the simulation itself while the mouse is
+/// active.
+///
+/// # Return
+/// Returns `true` if the amount of boids changed.
+fn apply_mouse_tool(mouse_tool: MouseTool, position: Vector2, sim_data: &mut SimData) -> bool {
+    let boid_settings = &sim_data.boid_settings;
+    let tool = boid_settings.tool_settings_of(mouse_tool);
+    match mouse_tool {
+        MouseTool::Spawn => {
+            for _ in 0..tool.count {
+                let offset = Vector2::new(fastrand::f32() - 0.5, fastrand::f32() - 0.5);
+                spawn_boid(
+                    &mut sim_data.boid_data,
+                    boid_settings,
+                    position + offset * (tool.range * 2.0),
+                    Vector2::ZERO,
+                );
+            }
+            tool.count > 0
+        }
+        MouseTool::Delete => {
+            let removed = remove_boids_within(
+                &mut sim_data.boid_data,
+                boid_settings,
+                position,
+                tool.range,
+                tool.count,
+            );
+            sim_data
+                .boid_settings
+                .sync_predator_count(&sim_data.boid_data);
+            removed > 0
+        }
+        _ => false,
     }
+}
+
+/// Handles mouse related input `event`s.
+///
+/// While drawing walls, dragging the left mouse button paints walls and
+/// dragging the right mouse button erases them. Otherwise, holding the left
+/// mouse button applies the current mouse tool, while clicking the right mouse
+/// button drops an attractor. Dragging the middle mouse button spawns boids,
+/// or deletes them while holding control, independent of the current tool.
+fn on_mouse_event(event: MouseEvent, sim_data: &mut SimData) {
     // Set mouse position to middle of character
-    boid_settings.set_mouse_position(event.column as f32 + 0.5, event.row as f32 * 2.0 + 1.0);
+    let position = Vector2::new(event.column as f32 + 0.5, event.row as f32 * 2.0 + 1.0);
+    sim_data
+        .boid_settings
+        .set_mouse_position(position.x, position.y);
+    // Whether the boid count or attractors shown in the menu changed.
+    let mut menu_changed = false;
+    if sim_data.sim_settings.drawing_walls {
+        let walls = &mut sim_data.environment.walls;
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Drag(MouseButton::Left) => {
+                walls.paint(position, WALL_BRUSH_RADIUS);
+            }
+            MouseEventKind::Down(MouseButton::Right) | MouseEventKind::Drag(MouseButton::Right) => {
+                walls.erase(position, WALL_BRUSH_RADIUS);
+            }
+            _ => (),
+        }
+    } else {
+        sim_data.boid_settings.set_mouse_hovering(true);
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Drag(MouseButton::Left) => {
+                sim_data.boid_settings.set_mouse_active(true);
+                let mouse_tool = sim_data.boid_settings.mouse_tool;
+                menu_changed = apply_mouse_tool(mouse_tool, position, sim_data);
+            }
+            MouseEventKind::Up(MouseButton::Left) => {
+                sim_data.boid_settings.set_mouse_active(false);
+            }
+            MouseEventKind::Down(MouseButton::Right) => {
+                sim_data.environment.add_attractor(Attractor::new(
+                    position,
+                    ATTRACTOR_STRENGTH,
+                    ATTRACTOR_RADIUS,
+                    ATTRACTOR_DEPLETION,
+                ));
+                menu_changed = true;
+            }
+            MouseEventKind::Down(MouseButton::Middle)
+            | MouseEventKind::Drag(MouseButton::Middle) => {
+                let mouse_tool = if event.modifiers.contains(KeyModifiers::CONTROL) {
+                    MouseTool::Delete
+                } else {
+                    MouseTool::Spawn
+                };
+                menu_changed = apply_mouse_tool(mouse_tool, position, sim_data);
+            }
+            _ => (),
+        }
+    }
+    if menu_changed {
+        refresh_menu(sim_data);
+    }
 }
 
 /// Handles the logic for when the terminal window is resized.
@@ -235,6 +691,10 @@ fn on_resize(new_columns: usize, new_rows: usize, sim_data: &mut SimData) {
     sim_data
         .boid_settings
         .update_window(new_columns, new_rows * 2, &mut sim_data.boid_data);
+    sim_data
+        .environment
+        .update_window(new_columns, new_rows * 2);
+    sim_data.flock_3d.update_window(new_columns, new_rows * 2);
 }
 
 /// Reads and handles all the input currently in the queue.
@@ -248,16 +708,16 @@ fn handle_input(sim_data: &mut SimData) -> Result<()> {
         let event = read()?;
         match event {
             Event::Key(key_event) => on_key_event(key_event, sim_data)?,
-            Event::Mouse(mouse_event) => on_mouse_event(mouse_event, &mut sim_data.boid_settings),
-            Event::FocusGained => {
-                // Regain mouse control
-                sim_data
-                    .boid_settings
-                    .set_mouse_force(MOUSE_FORCE, MOUSE_RANGE);
+            // The mouse tools only apply to the 2D simulation.
+            Event::Mouse(mouse_event) if !sim_data.sim_settings.three_d => {
+                on_mouse_event(mouse_event, sim_data)
             }
             Event::FocusLost => {
                 // Lose mouse control
-                sim_data.boid_settings.set_mouse_force(0.0, 0.0);
+                sim_data
+                    .boid_settings
+                    .set_mouse_active(false)
+                    .set_mouse_hovering(false);
             }
             Event::Resize(c, r) => on_resize(c as usize, r as usize, sim_data),
             _ => (),
@@ -265,12 +725,15 @@ fn handle_input(sim_data: &mut SimData) -> Result<()> {
         // Only let menu handle input while it is visible
         if sim_data.sim_settings.menu_visible
             && let Some(changed_item) = menu::handle_input(&mut sim_data.menu, &event)
-        {
-            on_menu_change(
+            && on_menu_change(
                 changed_item,
                 &mut sim_data.boid_settings,
                 &mut sim_data.boid_data,
-            );
+                &mut sim_data.environment,
+                &mut sim_data.sim_settings.menu_state,
+            )
+        {
+            refresh_menu(sim_data);
         }
     }
     Ok(())
@@ -281,14 +744,72 @@ fn handle_input(sim_data: &mut SimData) -> Result<()> {
 /// set in the `sim_settings`.
 fn sim_delay(start: Instant, sim_settings: &SimulationSettings) -> f32 {
     let current_frame_time = start.elapsed();
-    if current_frame_time.as_millis() < sim_settings.frame_time.as_millis() {
-        sleep(FRAME_TIME.abs_diff(current_frame_time));
-        FRAME_TIME.as_millis() as f32 / 1000.0
+    if current_frame_time < sim_settings.frame_time {
+        sleep(sim_settings.frame_time.abs_diff(current_frame_time));
+        sim_settings.frame_time.as_secs_f32()
     } else {
-        current_frame_time.as_millis() as f32 / 1000.0
+        current_frame_time.as_secs_f32()
     }
 }
 
+/// Updates the boids, after which the attractors are depleted by the boids
+/// within their radius, across the `delta` time frame. In 3D, only the 3D
+/// flock is updated.
+fn update_world(sim_data: &mut SimData, delta: f32) {
+    if sim_data.sim_settings.three_d {
+        sim_data.flock_3d.update(&sim_data.boid_settings, delta);
+        return;
+    }
+    if let Some(clock) = &mut sim_data.sim_settings.clock {
+        let (width, height) = (sim_data.boid_settings.width, sim_data.boid_settings.height);
+        clock.update(&mut sim_data.environment, width, height);
+    }
+    sim_data.environment.assign_formation(&sim_data.boid_data);
+    update_boids(
+        &mut sim_data.boid_data,
+        &sim_data.boid_settings,
+        &sim_data.environment,
+        delta,
+    );
+    if sim_data.boid_settings.ecosystem {
+        sim_data
+            .boid_settings
+            .sync_predator_count(&sim_data.boid_data);
+    }
+    sim_data
+        .environment
+        .deplete_attractors(sim_data.boid_data.iter_all(), delta);
+    sim_data.environment.flow.update(delta);
+}
+
+/// Updates the boids for the `frame_duration` of the last frame and returns
+/// how far the rendering should interpolate between the previous and current
+/// positions of the boids.
+///
+/// With a fixed timestep, the `frame_duration` is added to the `accumulator`,
+/// from which as many updates of [`FIXED_STEP`] as fit are taken. Otherwise
+/// the boids are updated once with the `frame_duration` itself.
+fn step_boids(sim_data: &mut SimData, accumulator: &mut f32, frame_duration: f32) -> f32 {
+    if !sim_data.sim_settings.fixed_timestep {
+        update_world(sim_data, frame_duration * TIME_SCALE);
+        return 1.0;
+    }
+
+    *accumulator += frame_duration;
+    let mut steps = 0;
+    while *accumulator >= FIXED_STEP {
+        if steps == MAX_STEPS_PER_FRAME {
+            // Drop the remaining time rather than falling further behind.
+            *accumulator = 0.0;
+            break;
+        }
+        update_world(sim_data, FIXED_STEP * TIME_SCALE);
+        *accumulator -= FIXED_STEP;
+        steps += 1;
+    }
+    *accumulator / FIXED_STEP
+}
+
 /// Performs the main simulation loop of the boids.
 /// This involves the handling of input, updating of the boids
 /// and rendering them to the terminal.
@@ -300,6 +821,8 @@ fn sim_delay(start: Instant, sim_settings: &SimulationSettings) -> f32 {
 fn simulate(mut sim_data: SimData) -> Result<()> {
     let mut stdout = stdout();
     let mut last_duration: f32 = 0.02;
+    // Real time not yet covered by fixed timestep updates.
+    let mut accumulator: f32 = 0.0;
     while sim_data.sim_settings.running {
         let now = Instant::now();
         let size = window_size()?;
@@ -311,21 +834,41 @@ fn simulate(mut sim_data: SimData) -> Result<()> {
             continue;
         }
 
-        // TODO: remove the need for this timescale by using sane parameters.
-        const TIME_SCALE: f32 = 10.0;
-        update_boids(
-            &mut sim_data.boid_data,
-            &sim_data.boid_settings,
-            last_duration * TIME_SCALE,
-        );
+        let alpha = step_boids(&mut sim_data, &mut accumulator, last_duration);
+
+        if sim_data.sim_settings.three_d {
+            sim_data.sim_settings.camera.orbit(last_duration);
+            draw_boids_3d(
+                &mut stdout,
+                &sim_data.flock_3d,
+                &sim_data.sim_settings.camera,
+                &size,
+                &sim_data.sim_settings,
+                alpha,
+            )?;
+        } else {
+            draw_boids(
+                &mut stdout,
+                sim_data.boid_data.iter_all(),
+                &size,
+                &sim_data.sim_settings,
+                &sim_data.boid_settings,
+                &sim_data.environment,
+                alpha,
+            )?;
+        }
 
-        draw_boids(
-            &mut stdout,
-            sim_data.boid_data.iter_all(),
-            &size,
-            &sim_data.sim_settings,
-            &sim_data.boid_settings,
-        )?;
+        if let Some(status) = &sim_data.sim_settings.status {
+            draw_status(&mut stdout, status, &size)?;
+        }
+        let tool = if sim_data.sim_settings.three_d {
+            "3D"
+        } else if sim_data.sim_settings.drawing_walls {
+            "Walls"
+        } else {
+            sim_data.boid_settings.mouse_tool.name()
+        };
+        draw_tool_indicator(&mut stdout, tool, &size)?;
 
         if sim_data.sim_settings.menu_visible {
             draw_menu(&sim_data.menu)?;
@@ -386,26 +929,71 @@ fn revert_stdout() -> Result<()> {
 }
 
 /// Starts the main boid simulation loop. Before this loop is started, the
-/// terminal is set up and afterwards, its reverted to its normal behavior.
+/// files given in the `args` are loaded and the terminal is set up and
+/// afterwards, its reverted to its normal behavior.
 ///
 /// # Errors
 ///
-/// This function will return an error if it catches any of the io errors
-/// resulting from terminal manipulation.
-fn start() -> Result<()> {
+/// This function will return an error if it fails to load the files given in
+/// the `args`, or if it catches any of the io errors resulting from terminal
+/// manipulation.
+fn start(args: Args) -> Result<()> {
+    // Load files before switching screens, keeping any errors readable.
+    let paths = match &args.paths {
+        Some(file_path) => Path::load(file_path)?,
+        None => Vec::new(),
+    };
+    let affinity = match &args.affinity {
+        Some(file_path) => Some(AffinityMatrix::load(file_path)?),
+        None => None,
+    };
+    let bitmap = match (&args.image, &args.text) {
+        (Some(file_path), _) => Some(Bitmap::load_pbm(file_path)?),
+        (None, Some(text)) => Some(Bitmap::from_text(text)),
+        (None, None) => None,
+    };
+
     prepare_stdout()?;
 
-    let boid_settings = match boid_settings_init() {
+    let mut boid_settings = match boid_settings_init() {
         Ok(settings) => settings,
         Err(e) => {
             revert_stdout()?;
             return Err(e);
         }
     };
-    let boid_data: Grid<Boid> = populate(COUNT, GROUP_COUNT, &boid_settings);
-    let sim_settings = SimulationSettings::init();
-    let menu = setup_menu(&boid_settings);
-    let sim_data = SimData::new(sim_settings, boid_settings, boid_data, menu);
+    let (width, height) = (boid_settings.width, boid_settings.height);
+    let mut boid_data: Grid<Boid> = populate(COUNT, GROUP_COUNT, &boid_settings);
+    boid_settings
+        .set_group_count(GROUP_COUNT, &mut boid_data)
+        .set_predator_count(PREDATOR_COUNT, &mut boid_data);
+    if let Some(affinity) = &affinity {
+        boid_settings.set_affinity_matrix(affinity.clone(), &mut boid_data);
+    }
+    let flock_3d = Flock3D::new(COUNT_3D, &boid_settings);
+    let sim_settings = SimulationSettings::init(args.utc_offset, affinity);
+    let mut environment = Environment::new(width, height);
+    environment
+        .set_paths(paths)
+        .set_formation(bitmap.map(|bitmap| Formation::new(bitmap, width, height)));
+    environment
+        .flow
+        .set_generator(FLOW_GENERATOR, FLOW_STRENGTH, WIND_DIRECTION)
+        .set_visible(SHOW_FLOW);
+    let menu = setup_menu(
+        &boid_settings,
+        &boid_data,
+        &environment,
+        &sim_settings.menu_state,
+    );
+    let sim_data = SimData::new(
+        sim_settings,
+        boid_settings,
+        boid_data,
+        flock_3d,
+        environment,
+        menu,
+    );
     let result = simulate(sim_data);
 
     revert_stdout()?;
@@ -414,5 +1002,6 @@ fn start() -> Result<()> {
 }
 
 fn main() -> Result<()> {
-    start()
+    let args = Args::parse(std::env::args().skip(1))?;
+    start(args)
 }
@@ -7,6 +7,7 @@
 //! between items in the menu and actions in the code using it.
 // TODO: Give some examples on how to render and how to handle input events
 
+use std::borrow::Cow;
 use std::io::{Result, Stdout, stdout};
 
 use crossterm::{
@@ -23,13 +24,15 @@ use crossterm::{
 /// along with values, settings and generic identifiers, meant for
 /// the calling functions to identify which settings were changed.
 pub enum MenuItem<T> {
-    /// An integer number input with specified `min` and `max` constraints.
+    /// An integer number input with specified `min`, `max` and
+    /// `step_size` as constraints when changing the value.
     #[allow(dead_code)]
     IntSlider {
         id: T,
         current: i32,
         min: i32,
         max: i32,
+        step_size: i32,
     },
     /// A decimal number input with specified `min`, `max` and
     /// `step_size` as constraints when changing the value.
@@ -64,9 +67,13 @@ impl<T> MenuItem<T> {
     fn alter(&mut self, factor: i32) {
         match self {
             MenuItem::IntSlider {
-                current, max, min, ..
+                current,
+                max,
+                min,
+                step_size,
+                ..
             } => {
-                *current = (*max).min(*current + factor).max(*min);
+                *current = (*max).min(*current + factor * *step_size).max(*min);
             }
             MenuItem::FloatSlider {
                 current,
@@ -96,7 +103,7 @@ pub struct Menu<T> {
     /// between this vector and the rendered elements.
     items: Vec<MenuItem<T>>,
     /// The names of the respective menu items.
-    names: Vec<&'static str>,
+    names: Vec<Cow<'static, str>>,
     /// The index of the currently selected element in the menu.
     current: usize,
     width: u16,
@@ -114,14 +121,32 @@ impl<T> Menu<T> {
         }
     }
 
-    /// Add a new `menu_item` to the end of the menu.
+    /// Add a new `menu_item` to the end of the menu, shown with the given
+    /// `name`, which is either fixed or generated at runtime.
     #[allow(dead_code)]
-    pub fn add_menu_item(&mut self, menu_item: MenuItem<T>, name: &'static str) -> &mut Menu<T> {
+    pub fn add_menu_item(
+        &mut self,
+        menu_item: MenuItem<T>,
+        name: impl Into<Cow<'static, str>>,
+    ) -> &mut Menu<T> {
+        let name = name.into();
+        self.width = self.width.max(name.chars().count() as u16);
         self.items.push(menu_item);
         self.names.push(name);
-        self.width = self.width.max(name.chars().count() as u16);
         self
     }
+
+    /// Returns the index of the currently selected item.
+    pub fn selected(&self) -> usize {
+        self.current
+    }
+
+    /// Selects the item at `index`, if it exists.
+    pub fn select(&mut self, index: usize) {
+        if index < self.items.len() {
+            self.current = index;
+        }
+    }
 }
 
 /// Handles the input `key_event` in case of a [`KeyEvent`], allowing for the
@@ -190,12 +215,18 @@ pub fn draw_item<T>(item: &MenuItem<T>, stdout: &mut Stdout) -> Result<()> {
         MenuItem::IntSlider { current, .. } => {
             queue!(stdout, Print("< "), Print(current), Print(" >"))?
         }
-        MenuItem::FloatSlider { current, .. } => queue!(
-            stdout,
-            Print("< "),
-            Print(format!("{:.2}", current)),
-            Print(" >")
-        )?,
+        MenuItem::FloatSlider {
+            current, step_size, ..
+        } => {
+            // Show at least two decimals, or more when the steps are smaller.
+            let precision = (-step_size.log10()).ceil().max(2.0) as usize;
+            queue!(
+                stdout,
+                Print("< "),
+                Print(format!("{:.*}", precision, current)),
+                Print(" >")
+            )?
+        }
         MenuItem::Toggle { current, .. } => {
             if *current {
                 queue!(stdout, Print("[x]"))?;
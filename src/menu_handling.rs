@@ -3,6 +3,14 @@
 //! # Menu Handling
 //! Allows for the creation and handling of a menu for the boid simulation.
 //! The menu currently gives control over the:
+//! - Boids
+//!     - Boid count
+//! - Groups
+//!     - Group count
+//!     - Edited group, selecting whether the range, rule weight, minimum
+//!       speed and gravity options edit the shared settings or override
+//!       those of a single group
+//!     - Affinity between the selected row and column group
 //! - Range
 //!     - Protected range
 //!     - Visible range
@@ -10,30 +18,121 @@
 //!     - Separation force
 //!     - Cohesion force
 //!     - Alignment force
+//! - Kernels
+//!     - Separation kernel
+//!     - Cohesion kernel
+//!     - Alignment kernel
+//! - Perception
+//!     - View angle
+//!     - View falloff
+//!     - Neighbour mode
+//!     - Nearest neighbours
+//!     - Neighbour sampling
+//!     - Sample budget
+//! - Simulation
+//!     - Update mode
+//!     - Integrator
 //! - Border
+//!     - Border
 //!     - Turning force
 //!     - Border margin
+//! - Obstacles
+//!     - Avoidance force
+//!     - Look ahead distance
+//!     - Wall force
+//! - Paths
+//!     - Path force
+//!     - Waypoint radius
+//! - Formation
+//!     - Formation force
+//! - Flow
+//!     - Flow field
+//!     - Flow strength
+//!     - Wind direction
+//!     - Show flow
 //! - Other
 //!     - Minimum speed
 //!     - Gravity
 //!     - Noise force
+//!     - Noise model
 //!     - Friction coefficient
+//! - Predators
+//!     - Predator count
+//!     - Chase force
+//!     - Flee force
+//!     - Predator speed
+//! - Ecosystem
+//!     - Ecosystem
+//!     - Energy decay
+//!     - Feeding rate
+//!     - Grazing rate
+//!     - Reproduction energy
+//!     - Max age
+//! - Crabs
+//!     - Crab mode
+//!     - Hop rate
+//!     - Hop speed
+//!     - Scatter force
+//!     - Scatter range
+//! - Perching
+//!     - Perching
+//!     - Perch rate
+//!     - Perch time
+//!     - Takeoff count
+//!     - Disturb range
+//! - Mouse
+//!     - Mouse tool
+//!     - Tool strength, the force of the current tool, or tool count, the
+//!       amount of boids the spawn and delete tools add or remove at a time
+//!     - Tool range
+//! - Attractors, listing for each of the existing attractors its
+//!     - Strength
+//!     - Radius
+//!     - Depletion
+//!     - Delete, removing the attractor
 //!
 //! ## Example
 //! ```no-run
-//! let mut menu: Menu<MenuID> = setup_menu(&boid_settings);
+//! let mut menu: Menu<MenuID> = setup_menu(&boid_settings, &boid_data, &environment, &menu_state);
 //! ...
 //! if let Some(item) = menu::handle_input(&mut menu...) {
-//!     on_menu_change(event, ...);
+//!     if on_menu_change(event, ..., &mut menu_state) {
+//!         menu = setup_menu(&boid_settings, &boid_data, &environment, &menu_state);
+//!     }
 //! }
 //! ```
 
-use crate::boids::{Boid, BoidSettings};
+use crate::boids::{
+    Boid, BoidSettings, BorderSettings, Environment, Integrator, Kernel, MAX_POPULATION, MouseTool,
+    NeighbourMode, NeighbourSampling, NoiseModel, UpdateMode, affinity::MAX_GROUPS,
+    flow::FlowGenerator, set_boid_count, settings::GroupOverrides,
+};
 use crate::grid::Grid;
 use crate::menu::{Menu, MenuItem};
 
+/// The selections made in the menu that are not settings themselves, but
+/// determine which settings the other options change.
+#[derive(Default)]
+pub struct MenuState {
+    /// The group whose settings are edited, or `None` when editing the
+    /// settings shared by all groups.
+    pub edited_group: Option<u8>,
+    /// The group whose affinity is edited.
+    pub affinity_row: u8,
+    /// The group towards which the affinity is edited.
+    pub affinity_column: u8,
+}
+
 /// Identifiers for each option in the used menu.
 pub enum MenuID {
+    BoidCount,
+
+    GroupCount,
+    EditedGroup,
+    AffinityRow,
+    AffinityColumn,
+    Affinity,
+
     ProtectedRange,
     VisibleRange,
 
@@ -41,70 +140,697 @@ pub enum MenuID {
     CohesionForce,
     AlignmentForce,
 
+    SeparationKernel,
+    CohesionKernel,
+    AlignmentKernel,
+
     MinSpeed,
 
+    ViewAngle,
+    ViewFalloff,
+    NeighbourMode,
+    NearestNeighbours,
+    NeighbourSampling,
+    SampleBudget,
+    UpdateMode,
+    Integrator,
+
+    Border,
     TurnForce,
     Margin,
 
+    AvoidForce,
+    LookAhead,
+    WallForce,
+
+    PathForce,
+    WaypointRadius,
+
+    FormationForce,
+
+    FlowGenerator,
+    FlowStrength,
+    WindDirection,
+    ShowFlow,
+
     Gravity,
     NoiseForce,
+    NoiseModel,
     FrictionCoefficient,
+
+    PredatorCount,
+    ChaseForce,
+    FleeForce,
+    PredatorSpeed,
+
+    Ecosystem,
+    EnergyDecay,
+    FeedingRate,
+    GrazingRate,
+    ReproductionEnergy,
+    MaxAge,
+
+    CrabMode,
+    HopRate,
+    HopSpeed,
+    ScatterForce,
+    ScatterRange,
+
+    Perching,
+    PerchRate,
+    PerchTime,
+    TakeoffCount,
+    DisturbRange,
+
+    MouseTool,
+    ToolStrength,
+    ToolCount,
+    ToolRange,
+
+    AttractorStrength(usize),
+    AttractorRadius(usize),
+    AttractorDepletion(usize),
+    DeleteAttractor(usize),
+}
+
+/// Applies the `edit` to the overrides of the given `group`.
+fn edit_group(
+    boid_settings: &mut BoidSettings,
+    boid_data: &mut Grid<Boid>,
+    group: u8,
+    edit: impl FnOnce(&mut GroupOverrides),
+) {
+    let mut overrides = boid_settings.group_overrides[group as usize];
+    edit(&mut overrides);
+    boid_settings.set_group_overrides(group, overrides, boid_data);
 }
 
-/// Updates the respective settings in the `boid_data` or `boid_settings` given
-/// the `changed_item` event generated by the menu.
+/// Updates the respective settings in the `boid_data`, `boid_settings` or
+/// `environment` given the `changed_item` event generated by the menu. Group
+/// specific settings are changed for the group selected in the `menu_state`,
+/// while the attractor settings are changed for the attractor in the list.
+///
+/// # Return
+/// Returns `true` if the menu must be recreated to reflect the changes,
+/// `false` otherwise.
 pub fn on_menu_change(
     changed_item: &MenuItem<MenuID>,
     boid_settings: &mut BoidSettings,
     boid_data: &mut Grid<Boid>,
-) {
-    if let MenuItem::FloatSlider { id, current, .. } = changed_item {
-        match id {
-            MenuID::ProtectedRange => {
-                boid_settings.set_protected_range(*current, boid_data);
+    environment: &mut Environment,
+    menu_state: &mut MenuState,
+) -> bool {
+    let edited_group = &mut menu_state.edited_group;
+    match changed_item {
+        MenuItem::FloatSlider { id, current, .. } => match id {
+            MenuID::ProtectedRange => match *edited_group {
+                Some(group) => edit_group(boid_settings, boid_data, group, |overrides| {
+                    overrides.protected_range = Some(*current)
+                }),
+                None => {
+                    boid_settings.set_protected_range(*current, boid_data);
+                }
+            },
+            MenuID::VisibleRange => match *edited_group {
+                Some(group) => edit_group(boid_settings, boid_data, group, |overrides| {
+                    overrides.visible_range = Some(*current)
+                }),
+                None => {
+                    boid_settings.set_visible_range(*current, boid_data);
+                }
+            },
+            MenuID::CohesionForce => match *edited_group {
+                Some(group) => edit_group(boid_settings, boid_data, group, |overrides| {
+                    overrides.cohesion = Some(*current)
+                }),
+                None => {
+                    boid_settings.set_cohesion_force(*current);
+                }
+            },
+            MenuID::SeperationForce => match *edited_group {
+                Some(group) => edit_group(boid_settings, boid_data, group, |overrides| {
+                    overrides.separation = Some(*current)
+                }),
+                None => {
+                    boid_settings.set_separation_force(*current);
+                }
+            },
+            MenuID::AlignmentForce => match *edited_group {
+                Some(group) => edit_group(boid_settings, boid_data, group, |overrides| {
+                    overrides.alignment = Some(*current)
+                }),
+                None => {
+                    boid_settings.set_alignment_force(*current);
+                }
+            },
+            MenuID::MinSpeed => match *edited_group {
+                Some(group) => edit_group(boid_settings, boid_data, group, |overrides| {
+                    overrides.min_speed = Some(*current)
+                }),
+                None => {
+                    boid_settings.set_min_speed(*current);
+                }
+            },
+            MenuID::ViewAngle => {
+                boid_settings.set_view(*current, boid_settings.view_falloff);
             }
-            MenuID::VisibleRange => {
-                boid_settings.set_visible_range(*current, boid_data);
+            MenuID::TurnForce => {
+                boid_settings.set_turn_force(*current);
             }
-            MenuID::CohesionForce => {
-                boid_settings.set_cohesion_force(*current);
+            MenuID::Margin => {
+                boid_settings.set_margin(*current);
             }
-            MenuID::SeperationForce => {
-                boid_settings.set_separation_force(*current);
+            MenuID::AvoidForce => {
+                boid_settings.set_obstacle_avoidance(*current, boid_settings.look_ahead);
             }
-            MenuID::AlignmentForce => {
-                boid_settings.set_alignment_force(*current);
+            MenuID::LookAhead => {
+                boid_settings.set_obstacle_avoidance(boid_settings.avoid_force, *current);
             }
-            MenuID::MinSpeed => {
-                boid_settings.set_min_speed(*current);
+            MenuID::WallForce => {
+                boid_settings.set_wall_force(*current);
             }
-            MenuID::TurnForce => {
-                boid_settings.set_turn_force(*current);
+            MenuID::PathForce => {
+                boid_settings.set_path_following(*current, boid_settings.waypoint_radius);
             }
-            MenuID::Margin => {
-                boid_settings.set_margin(*current);
+            MenuID::WaypointRadius => {
+                boid_settings.set_path_following(boid_settings.path_force, *current);
             }
-            MenuID::Gravity => {
-                boid_settings.set_gravity(*current);
+            MenuID::FormationForce => {
+                boid_settings.set_formation_force(*current);
             }
+            MenuID::FlowStrength => {
+                let flow = &mut environment.flow;
+                flow.set_generator(flow.generator, *current, flow.direction);
+            }
+            MenuID::WindDirection => {
+                let flow = &mut environment.flow;
+                flow.set_generator(flow.generator, flow.strength, *current);
+            }
+            MenuID::Gravity => match *edited_group {
+                Some(group) => edit_group(boid_settings, boid_data, group, |overrides| {
+                    overrides.gravity = Some(*current)
+                }),
+                None => {
+                    boid_settings.set_gravity(*current);
+                }
+            },
             MenuID::NoiseForce => {
                 boid_settings.set_noise(*current);
             }
             MenuID::FrictionCoefficient => {
                 boid_settings.set_friction(*current, boid_settings.squared_friction);
             }
+            MenuID::ChaseForce => {
+                boid_settings.set_predator_forces(*current, boid_settings.flee_force);
+            }
+            MenuID::FleeForce => {
+                boid_settings.set_predator_forces(boid_settings.chase_force, *current);
+            }
+            MenuID::PredatorSpeed => {
+                boid_settings.set_predator_speed(*current);
+            }
+            MenuID::AttractorStrength(index) => {
+                if let Some(attractor) = environment.attractors.get_mut(*index) {
+                    attractor.strength = *current;
+                }
+            }
+            MenuID::AttractorRadius(index) => {
+                if let Some(attractor) = environment.attractors.get_mut(*index) {
+                    attractor.radius = *current;
+                }
+            }
+            MenuID::AttractorDepletion(index) => {
+                if let Some(attractor) = environment.attractors.get_mut(*index) {
+                    attractor.depletion = *current;
+                }
+            }
+            MenuID::EnergyDecay => {
+                boid_settings.set_ecosystem(
+                    boid_settings.ecosystem,
+                    *current,
+                    boid_settings.feeding_rate,
+                    boid_settings.grazing_rate,
+                    boid_settings.reproduction_energy,
+                    boid_settings.max_age,
+                );
+            }
+            MenuID::FeedingRate => {
+                boid_settings.set_ecosystem(
+                    boid_settings.ecosystem,
+                    boid_settings.energy_decay,
+                    *current,
+                    boid_settings.grazing_rate,
+                    boid_settings.reproduction_energy,
+                    boid_settings.max_age,
+                );
+            }
+            MenuID::GrazingRate => {
+                boid_settings.set_ecosystem(
+                    boid_settings.ecosystem,
+                    boid_settings.energy_decay,
+                    boid_settings.feeding_rate,
+                    *current,
+                    boid_settings.reproduction_energy,
+                    boid_settings.max_age,
+                );
+            }
+            MenuID::ReproductionEnergy => {
+                boid_settings.set_ecosystem(
+                    boid_settings.ecosystem,
+                    boid_settings.energy_decay,
+                    boid_settings.feeding_rate,
+                    boid_settings.grazing_rate,
+                    *current,
+                    boid_settings.max_age,
+                );
+            }
+            MenuID::MaxAge => {
+                boid_settings.set_ecosystem(
+                    boid_settings.ecosystem,
+                    boid_settings.energy_decay,
+                    boid_settings.feeding_rate,
+                    boid_settings.grazing_rate,
+                    boid_settings.reproduction_energy,
+                    *current,
+                );
+            }
+            MenuID::HopRate => {
+                boid_settings.set_crab_mode(
+                    boid_settings.crab_mode,
+                    *current,
+                    boid_settings.hop_speed,
+                    boid_settings.scatter_force,
+                    boid_settings.scatter_range,
+                );
+            }
+            MenuID::HopSpeed => {
+                boid_settings.set_crab_mode(
+                    boid_settings.crab_mode,
+                    boid_settings.hop_rate,
+                    *current,
+                    boid_settings.scatter_force,
+                    boid_settings.scatter_range,
+                );
+            }
+            MenuID::ScatterForce => {
+                boid_settings.set_crab_mode(
+                    boid_settings.crab_mode,
+                    boid_settings.hop_rate,
+                    boid_settings.hop_speed,
+                    *current,
+                    boid_settings.scatter_range,
+                );
+            }
+            MenuID::ScatterRange => {
+                boid_settings.set_crab_mode(
+                    boid_settings.crab_mode,
+                    boid_settings.hop_rate,
+                    boid_settings.hop_speed,
+                    boid_settings.scatter_force,
+                    *current,
+                );
+            }
+            MenuID::PerchRate => {
+                boid_settings.set_perching(
+                    boid_settings.perching,
+                    *current,
+                    boid_settings.perch_time,
+                    boid_settings.takeoff_count,
+                    boid_settings.disturb_range,
+                );
+            }
+            MenuID::PerchTime => {
+                boid_settings.set_perching(
+                    boid_settings.perching,
+                    boid_settings.perch_rate,
+                    *current,
+                    boid_settings.takeoff_count,
+                    boid_settings.disturb_range,
+                );
+            }
+            MenuID::DisturbRange => {
+                boid_settings.set_perching(
+                    boid_settings.perching,
+                    boid_settings.perch_rate,
+                    boid_settings.perch_time,
+                    boid_settings.takeoff_count,
+                    *current,
+                );
+            }
+            MenuID::ToolStrength => {
+                let tool = boid_settings.tool_settings();
+                boid_settings.set_tool_settings(boid_settings.mouse_tool, *current, tool.range);
+            }
+            MenuID::ToolRange => {
+                let tool = boid_settings.tool_settings();
+                boid_settings.set_tool_settings(boid_settings.mouse_tool, tool.strength, *current);
+            }
+            MenuID::Affinity => {
+                boid_settings.set_affinity(
+                    menu_state.affinity_row,
+                    menu_state.affinity_column,
+                    *current,
+                );
+            }
+            _ => (),
+        },
+        MenuItem::IntSlider { id, current, .. } => match id {
+            MenuID::BoidCount => {
+                set_boid_count(boid_data, boid_settings, *current as usize);
+                return true;
+            }
+            MenuID::ToolCount => {
+                let tool = boid_settings.tool_settings();
+                boid_settings.set_tool_count(
+                    boid_settings.mouse_tool,
+                    *current as usize,
+                    tool.range,
+                );
+            }
+            MenuID::GroupCount => {
+                let group_count = *current as u8;
+                boid_settings.set_group_count(group_count, boid_data);
+                if edited_group.is_some_and(|group| group >= group_count) {
+                    *edited_group = None;
+                }
+                menu_state.affinity_row = menu_state.affinity_row.min(group_count - 1);
+                menu_state.affinity_column = menu_state.affinity_column.min(group_count - 1);
+                return true;
+            }
+            MenuID::EditedGroup => {
+                // Zero selects the settings shared by all groups.
+                *edited_group = (*current > 0).then(|| *current as u8 - 1);
+                return true;
+            }
+            MenuID::AffinityRow => {
+                menu_state.affinity_row = *current as u8;
+                return true;
+            }
+            MenuID::AffinityColumn => {
+                menu_state.affinity_column = *current as u8;
+                return true;
+            }
+            MenuID::PredatorCount => {
+                boid_settings.set_predator_count(*current as usize, boid_data);
+            }
+            MenuID::TakeoffCount => {
+                boid_settings.set_perching(
+                    boid_settings.perching,
+                    boid_settings.perch_rate,
+                    boid_settings.perch_time,
+                    *current as usize,
+                    boid_settings.disturb_range,
+                );
+            }
+            MenuID::NearestNeighbours => {
+                boid_settings.set_neighbour_mode(boid_settings.neighbour_mode, *current as usize);
+            }
+            MenuID::SampleBudget => {
+                boid_settings
+                    .set_neighbour_sampling(boid_settings.neighbour_sampling, *current as usize);
+            }
+            _ => (),
+        },
+        MenuItem::Toggle {
+            id: MenuID::ViewFalloff,
+            current,
+        } => {
+            boid_settings.set_view(boid_settings.view_angle, *current);
+        }
+        MenuItem::Toggle {
+            id: MenuID::ShowFlow,
+            current,
+        } => {
+            environment.flow.set_visible(*current);
+        }
+        MenuItem::Toggle {
+            id: MenuID::DeleteAttractor(index),
+            ..
+        } => {
+            if *index < environment.attractors.len() {
+                environment.attractors.remove(*index);
+            }
+            return true;
+        }
+        MenuItem::Toggle {
+            id: MenuID::Ecosystem,
+            current,
+        } => {
+            boid_settings.set_ecosystem(
+                *current,
+                boid_settings.energy_decay,
+                boid_settings.feeding_rate,
+                boid_settings.grazing_rate,
+                boid_settings.reproduction_energy,
+                boid_settings.max_age,
+            );
+        }
+        MenuItem::Toggle {
+            id: MenuID::CrabMode,
+            current,
+        } => {
+            boid_settings.set_crab_mode(
+                *current,
+                boid_settings.hop_rate,
+                boid_settings.hop_speed,
+                boid_settings.scatter_force,
+                boid_settings.scatter_range,
+            );
+        }
+        MenuItem::Toggle {
+            id: MenuID::Perching,
+            current,
+        } => {
+            boid_settings.set_perching(
+                *current,
+                boid_settings.perch_rate,
+                boid_settings.perch_time,
+                boid_settings.takeoff_count,
+                boid_settings.disturb_range,
+            );
+        }
+        MenuItem::Choice {
+            id: MenuID::NeighbourMode,
+            current,
+            ..
+        } => {
+            let neighbour_mode = match current {
+                0 => NeighbourMode::Metric,
+                _ => NeighbourMode::Topological,
+            };
+            boid_settings.set_neighbour_mode(neighbour_mode, boid_settings.k_nearest);
+        }
+        MenuItem::Choice {
+            id: MenuID::NeighbourSampling,
+            current,
+            ..
+        } => {
+            let neighbour_sampling = match current {
+                0 => NeighbourSampling::Exact,
+                1 => NeighbourSampling::Strided,
+                _ => NeighbourSampling::Reservoir,
+            };
+            boid_settings.set_neighbour_sampling(neighbour_sampling, boid_settings.sample_budget);
+        }
+        MenuItem::Choice {
+            id: MenuID::UpdateMode,
+            current,
+            ..
+        } => {
+            let update_mode = match current {
+                0 => UpdateMode::Sequential,
+                _ => UpdateMode::Synchronous,
+            };
+            boid_settings.set_update_mode(update_mode);
+        }
+        MenuItem::Choice {
+            id: MenuID::Integrator,
+            current,
+            ..
+        } => {
+            let integrator = match current {
+                0 => Integrator::ExplicitEuler,
+                1 => Integrator::SemiImplicitEuler,
+                2 => Integrator::VelocityVerlet,
+                _ => Integrator::Rk2,
+            };
+            boid_settings.set_integrator(integrator);
+        }
+        MenuItem::Choice {
+            id: MenuID::SeparationKernel,
+            current,
+            ..
+        } => {
+            boid_settings.set_kernels(
+                Kernel::OPTIONS[*current],
+                boid_settings.cohesion_kernel,
+                boid_settings.alignment_kernel,
+            );
+        }
+        MenuItem::Choice {
+            id: MenuID::CohesionKernel,
+            current,
+            ..
+        } => {
+            boid_settings.set_kernels(
+                boid_settings.separation_kernel,
+                Kernel::OPTIONS[*current],
+                boid_settings.alignment_kernel,
+            );
+        }
+        MenuItem::Choice {
+            id: MenuID::AlignmentKernel,
+            current,
+            ..
+        } => {
+            boid_settings.set_kernels(
+                boid_settings.separation_kernel,
+                boid_settings.cohesion_kernel,
+                Kernel::OPTIONS[*current],
+            );
+        }
+        MenuItem::Choice {
+            id: MenuID::NoiseModel,
+            current,
+            ..
+        } => {
+            let noise_model = match current {
+                0 => NoiseModel::White,
+                1 => NoiseModel::Wander,
+                2 => NoiseModel::OrnsteinUhlenbeck,
+                _ => NoiseModel::Levy,
+            };
+            boid_settings.set_noise_model(noise_model);
+        }
+        MenuItem::Choice {
+            id: MenuID::Border,
+            current,
+            ..
+        } => {
+            boid_settings.set_border(BorderSettings::OPTIONS[*current]);
         }
+        MenuItem::Choice {
+            id: MenuID::FlowGenerator,
+            current,
+            ..
+        } => {
+            let generator = match current {
+                0 => FlowGenerator::None,
+                1 => FlowGenerator::Wind,
+                2 => FlowGenerator::Vortex,
+                3 => FlowGenerator::Sink,
+                _ => FlowGenerator::CurlNoise,
+            };
+            let flow = &mut environment.flow;
+            flow.set_generator(generator, flow.strength, flow.direction);
+        }
+        MenuItem::Choice {
+            id: MenuID::MouseTool,
+            current,
+            ..
+        } => {
+            let mouse_tool = match current {
+                0 => MouseTool::Hover,
+                1 => MouseTool::Attract,
+                2 => MouseTool::Repel,
+                3 => MouseTool::Vortex,
+                4 => MouseTool::Wind,
+                5 => MouseTool::Spawn,
+                _ => MouseTool::Delete,
+            };
+            boid_settings.set_mouse_tool(mouse_tool);
+            return true;
+        }
+        _ => (),
     }
+    false
 }
 
 /// Sets up a menu which allows for the changing of the options represented in
-/// [`MenuID`].
-pub fn setup_menu(boid_settings: &BoidSettings) -> Menu<MenuID> {
+/// [`MenuID`]. The group specific options show the settings of the group
+/// selected in the `menu_state`, while the boid count is taken from the
+/// `boid_data`. The options of every attractor in the `environment` are listed
+/// at the end.
+pub fn setup_menu(
+    boid_settings: &BoidSettings,
+    boid_data: &Grid<Boid>,
+    environment: &Environment,
+    menu_state: &MenuState,
+) -> Menu<MenuID> {
+    let last_group = boid_settings.group_count() as i32 - 1;
+    // Either the shared settings or those resolved for the edited group.
+    let group_settings = match menu_state.edited_group {
+        Some(group) => *boid_settings.group(group),
+        None => boid_settings.shared_group(),
+    };
+
     let mut menu = Menu::new();
     menu.add_menu_item(
+        MenuItem::IntSlider {
+            id: MenuID::BoidCount,
+            current: boid_data.count as i32,
+            min: 0,
+            max: MAX_POPULATION as i32,
+            step_size: 100,
+        },
+        "Boid count",
+    )
+    .add_menu_item(
+        MenuItem::IntSlider {
+            id: MenuID::GroupCount,
+            current: boid_settings.group_count() as i32,
+            min: 1,
+            max: MAX_GROUPS as i32,
+            step_size: 1,
+        },
+        "Group count",
+    )
+    .add_menu_item(
+        MenuItem::IntSlider {
+            id: MenuID::EditedGroup,
+            current: menu_state.edited_group.map_or(0, |group| group as i32 + 1),
+            min: 0,
+            max: last_group + 1,
+            step_size: 1,
+        },
+        "Edited group (0: all)",
+    )
+    .add_menu_item(
+        MenuItem::IntSlider {
+            id: MenuID::AffinityRow,
+            current: menu_state.affinity_row as i32,
+            min: 0,
+            max: last_group,
+            step_size: 1,
+        },
+        "Affinity of group",
+    )
+    .add_menu_item(
+        MenuItem::IntSlider {
+            id: MenuID::AffinityColumn,
+            current: menu_state.affinity_column as i32,
+            min: 0,
+            max: last_group,
+            step_size: 1,
+        },
+        "Affinity towards group",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::Affinity,
+            current: boid_settings
+                .affinity
+                .get(menu_state.affinity_row, menu_state.affinity_column),
+            min: -5.0,
+            max: 5.0,
+            step_size: 0.1,
+        },
+        "Affinity",
+    )
+    .add_menu_item(
         MenuItem::FloatSlider {
             id: MenuID::ProtectedRange,
-            current: boid_settings.protected_range,
+            current: group_settings.protected_range,
             min: 0.0,
             max: 100.0,
             step_size: 0.1,
@@ -114,7 +840,7 @@ pub fn setup_menu(boid_settings: &BoidSettings) -> Menu<MenuID> {
     .add_menu_item(
         MenuItem::FloatSlider {
             id: MenuID::VisibleRange,
-            current: boid_settings.visible_range,
+            current: group_settings.visible_range,
             min: 1.0,
             max: 100.0,
             step_size: 0.1,
@@ -124,7 +850,7 @@ pub fn setup_menu(boid_settings: &BoidSettings) -> Menu<MenuID> {
     .add_menu_item(
         MenuItem::FloatSlider {
             id: MenuID::CohesionForce,
-            current: boid_settings.cohesion,
+            current: group_settings.cohesion,
             min: 0.0,
             max: 10.0,
             step_size: 0.01,
@@ -134,7 +860,7 @@ pub fn setup_menu(boid_settings: &BoidSettings) -> Menu<MenuID> {
     .add_menu_item(
         MenuItem::FloatSlider {
             id: MenuID::SeperationForce,
-            current: boid_settings.separation,
+            current: group_settings.separation,
             min: 0.0,
             max: 10.0,
             step_size: 0.01,
@@ -144,23 +870,141 @@ pub fn setup_menu(boid_settings: &BoidSettings) -> Menu<MenuID> {
     .add_menu_item(
         MenuItem::FloatSlider {
             id: MenuID::AlignmentForce,
-            current: boid_settings.alignment,
+            current: group_settings.alignment,
             min: 0.0,
             max: 10.0,
             step_size: 0.01,
         },
         "Alignment Force",
     )
+    .add_menu_item(
+        MenuItem::Choice {
+            id: MenuID::SeparationKernel,
+            current: Kernel::OPTIONS
+                .iter()
+                .position(|kernel| *kernel == boid_settings.separation_kernel)
+                .unwrap_or(0),
+            options: Kernel::OPTIONS.map(Kernel::name).to_vec(),
+        },
+        "Separation kernel",
+    )
+    .add_menu_item(
+        MenuItem::Choice {
+            id: MenuID::CohesionKernel,
+            current: Kernel::OPTIONS
+                .iter()
+                .position(|kernel| *kernel == boid_settings.cohesion_kernel)
+                .unwrap_or(0),
+            options: Kernel::OPTIONS.map(Kernel::name).to_vec(),
+        },
+        "Cohesion kernel",
+    )
+    .add_menu_item(
+        MenuItem::Choice {
+            id: MenuID::AlignmentKernel,
+            current: Kernel::OPTIONS
+                .iter()
+                .position(|kernel| *kernel == boid_settings.alignment_kernel)
+                .unwrap_or(0),
+            options: Kernel::OPTIONS.map(Kernel::name).to_vec(),
+        },
+        "Alignment kernel",
+    )
     .add_menu_item(
         MenuItem::FloatSlider {
             id: MenuID::MinSpeed,
-            current: boid_settings.min_speed,
+            current: group_settings.min_speed,
             min: 0.0,
             max: 10.0,
             step_size: 0.1,
         },
         "Min Speed",
     )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::ViewAngle,
+            current: boid_settings.view_angle,
+            min: 1.0,
+            max: 360.0,
+            step_size: 5.0,
+        },
+        "View angle",
+    )
+    .add_menu_item(
+        MenuItem::Toggle {
+            id: MenuID::ViewFalloff,
+            current: boid_settings.view_falloff,
+        },
+        "View falloff",
+    )
+    .add_menu_item(
+        MenuItem::Choice {
+            id: MenuID::NeighbourMode,
+            current: boid_settings.neighbour_mode as usize,
+            options: vec!["Metric", "Topological"],
+        },
+        "Neighbours",
+    )
+    .add_menu_item(
+        MenuItem::IntSlider {
+            id: MenuID::NearestNeighbours,
+            current: boid_settings.k_nearest as i32,
+            min: 1,
+            max: 50,
+            step_size: 1,
+        },
+        "Nearest neighbours",
+    )
+    .add_menu_item(
+        MenuItem::Choice {
+            id: MenuID::NeighbourSampling,
+            current: boid_settings.neighbour_sampling as usize,
+            options: vec!["Exact", "Strided", "Reservoir"],
+        },
+        "Neighbour sampling",
+    )
+    .add_menu_item(
+        MenuItem::IntSlider {
+            id: MenuID::SampleBudget,
+            current: boid_settings.sample_budget as i32,
+            min: 10,
+            max: 2000,
+            step_size: 10,
+        },
+        "Sample budget",
+    )
+    .add_menu_item(
+        MenuItem::Choice {
+            id: MenuID::UpdateMode,
+            current: boid_settings.update_mode as usize,
+            options: vec!["Sequential", "Synchronous"],
+        },
+        "Update mode",
+    )
+    .add_menu_item(
+        MenuItem::Choice {
+            id: MenuID::Integrator,
+            current: boid_settings.integrator as usize,
+            options: vec![
+                "Explicit Euler",
+                "Semi-implicit Euler",
+                "Velocity Verlet",
+                "RK2",
+            ],
+        },
+        "Integrator",
+    )
+    .add_menu_item(
+        MenuItem::Choice {
+            id: MenuID::Border,
+            current: BorderSettings::OPTIONS
+                .iter()
+                .position(|border| *border == boid_settings.border_settings)
+                .unwrap_or(0),
+            options: BorderSettings::OPTIONS.map(BorderSettings::name).to_vec(),
+        },
+        "Border",
+    )
     .add_menu_item(
         MenuItem::FloatSlider {
             id: MenuID::TurnForce,
@@ -181,10 +1025,105 @@ pub fn setup_menu(boid_settings: &BoidSettings) -> Menu<MenuID> {
         },
         "Margin",
     )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::AvoidForce,
+            current: boid_settings.avoid_force,
+            min: 0.0,
+            max: 10.0,
+            step_size: 0.1,
+        },
+        "Avoidance force",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::LookAhead,
+            current: boid_settings.look_ahead,
+            min: 0.0,
+            max: 50.0,
+            step_size: 0.5,
+        },
+        "Look ahead",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::WallForce,
+            current: boid_settings.wall_force,
+            min: 0.0,
+            max: 10.0,
+            step_size: 0.1,
+        },
+        "Wall force",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::PathForce,
+            current: boid_settings.path_force,
+            min: 0.0,
+            max: 10.0,
+            step_size: 0.1,
+        },
+        "Path force",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::WaypointRadius,
+            current: boid_settings.waypoint_radius,
+            min: 0.0,
+            max: 50.0,
+            step_size: 0.5,
+        },
+        "Waypoint radius",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::FormationForce,
+            current: boid_settings.formation_force,
+            min: 0.0,
+            max: 5.0,
+            step_size: 0.05,
+        },
+        "Formation force",
+    )
+    .add_menu_item(
+        MenuItem::Choice {
+            id: MenuID::FlowGenerator,
+            current: environment.flow.generator as usize,
+            options: vec!["None", "Wind", "Vortex", "Sink", "Curl noise"],
+        },
+        "Flow field",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::FlowStrength,
+            current: environment.flow.strength,
+            min: -10.0,
+            max: 10.0,
+            step_size: 0.1,
+        },
+        "Flow strength",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::WindDirection,
+            current: environment.flow.direction,
+            min: 0.0,
+            max: 360.0,
+            step_size: 15.0,
+        },
+        "Wind direction",
+    )
+    .add_menu_item(
+        MenuItem::Toggle {
+            id: MenuID::ShowFlow,
+            current: environment.flow.visible,
+        },
+        "Show flow",
+    )
     .add_menu_item(
         MenuItem::FloatSlider {
             id: MenuID::Gravity,
-            current: boid_settings.gravity,
+            current: group_settings.gravity,
             min: -5.0,
             max: 5.0,
             step_size: 0.01,
@@ -201,6 +1140,14 @@ pub fn setup_menu(boid_settings: &BoidSettings) -> Menu<MenuID> {
         },
         "Noise force",
     )
+    .add_menu_item(
+        MenuItem::Choice {
+            id: MenuID::NoiseModel,
+            current: boid_settings.noise_model as usize,
+            options: vec!["White", "Wander", "Ornstein-Uhlenbeck", "Levy flight"],
+        },
+        "Noise model",
+    )
     .add_menu_item(
         MenuItem::FloatSlider {
             id: MenuID::FrictionCoefficient,
@@ -210,6 +1157,286 @@ pub fn setup_menu(boid_settings: &BoidSettings) -> Menu<MenuID> {
             step_size: 0.01,
         },
         "Friction coefficient",
+    )
+    .add_menu_item(
+        MenuItem::IntSlider {
+            id: MenuID::PredatorCount,
+            current: boid_settings.predator_count as i32,
+            min: 0,
+            max: 100,
+            step_size: 1,
+        },
+        "Predator count",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::ChaseForce,
+            current: boid_settings.chase_force,
+            min: 0.0,
+            max: 10.0,
+            step_size: 0.1,
+        },
+        "Chase force",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::FleeForce,
+            current: boid_settings.flee_force,
+            min: 0.0,
+            max: 10.0,
+            step_size: 0.1,
+        },
+        "Flee force",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::PredatorSpeed,
+            current: boid_settings.predator_speed,
+            min: 0.0,
+            max: 100.0,
+            step_size: 0.5,
+        },
+        "Predator speed",
+    )
+    .add_menu_item(
+        MenuItem::Toggle {
+            id: MenuID::Ecosystem,
+            current: boid_settings.ecosystem,
+        },
+        "Ecosystem",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::EnergyDecay,
+            current: boid_settings.energy_decay,
+            min: 0.0,
+            max: 0.1,
+            step_size: 0.001,
+        },
+        "Energy decay",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::FeedingRate,
+            current: boid_settings.feeding_rate,
+            min: 0.0,
+            max: 1.0,
+            step_size: 0.01,
+        },
+        "Feeding rate",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::GrazingRate,
+            current: boid_settings.grazing_rate,
+            min: 0.0,
+            max: 0.1,
+            step_size: 0.001,
+        },
+        "Grazing rate",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::ReproductionEnergy,
+            current: boid_settings.reproduction_energy,
+            min: 1.0,
+            max: 10.0,
+            step_size: 0.1,
+        },
+        "Reproduction energy",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::MaxAge,
+            current: boid_settings.max_age,
+            min: 0.0,
+            max: 3000.0,
+            step_size: 50.0,
+        },
+        "Max age (0: none)",
+    )
+    .add_menu_item(
+        MenuItem::Toggle {
+            id: MenuID::CrabMode,
+            current: boid_settings.crab_mode,
+        },
+        "Crab mode",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::HopRate,
+            current: boid_settings.hop_rate,
+            min: 0.0,
+            max: 0.2,
+            step_size: 0.005,
+        },
+        "Hop rate",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::HopSpeed,
+            current: boid_settings.hop_speed,
+            min: 0.0,
+            max: 30.0,
+            step_size: 0.5,
+        },
+        "Hop speed",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::ScatterForce,
+            current: boid_settings.scatter_force,
+            min: 0.0,
+            max: 100.0,
+            step_size: 1.0,
+        },
+        "Scatter force",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::ScatterRange,
+            current: boid_settings.scatter_range,
+            min: 0.0,
+            max: 50.0,
+            step_size: 1.0,
+        },
+        "Scatter range",
+    )
+    .add_menu_item(
+        MenuItem::Toggle {
+            id: MenuID::Perching,
+            current: boid_settings.perching,
+        },
+        "Perching",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::PerchRate,
+            current: boid_settings.perch_rate,
+            min: 0.0,
+            max: 1.0,
+            step_size: 0.01,
+        },
+        "Perch rate",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::PerchTime,
+            current: boid_settings.perch_time,
+            min: 0.0,
+            max: 500.0,
+            step_size: 10.0,
+        },
+        "Perch time",
+    )
+    .add_menu_item(
+        MenuItem::IntSlider {
+            id: MenuID::TakeoffCount,
+            current: boid_settings.takeoff_count as i32,
+            min: 0,
+            max: 50,
+            step_size: 1,
+        },
+        "Takeoff count (0: none)",
+    )
+    .add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::DisturbRange,
+            current: boid_settings.disturb_range,
+            min: 0.0,
+            max: 50.0,
+            step_size: 1.0,
+        },
+        "Disturb range",
     );
+
+    let tool = boid_settings.tool_settings();
+    menu.add_menu_item(
+        MenuItem::Choice {
+            id: MenuID::MouseTool,
+            current: boid_settings.mouse_tool as usize,
+            options: vec![
+                "Hover", "Attract", "Repel", "Vortex", "Wind", "Spawn", "Delete",
+            ],
+        },
+        "Mouse tool",
+    );
+    if boid_settings.mouse_tool.changes_count() {
+        menu.add_menu_item(
+            MenuItem::IntSlider {
+                id: MenuID::ToolCount,
+                current: tool.count as i32,
+                min: 0,
+                max: 100,
+                step_size: 1,
+            },
+            "Tool count",
+        );
+    } else {
+        menu.add_menu_item(
+            MenuItem::FloatSlider {
+                id: MenuID::ToolStrength,
+                current: tool.strength,
+                min: 0.0,
+                max: 50.0,
+                step_size: 0.5,
+            },
+            "Tool strength",
+        );
+    }
+    menu.add_menu_item(
+        MenuItem::FloatSlider {
+            id: MenuID::ToolRange,
+            current: tool.range,
+            min: 1.0,
+            max: 100.0,
+            step_size: 1.0,
+        },
+        "Tool range",
+    );
+
+    for (index, attractor) in environment.attractors.iter().enumerate() {
+        let number = index + 1;
+        menu.add_menu_item(
+            MenuItem::FloatSlider {
+                id: MenuID::AttractorStrength(index),
+                current: attractor.strength,
+                min: -10.0,
+                max: 10.0,
+                step_size: 0.1,
+            },
+            format!("Attractor {number} strength"),
+        )
+        .add_menu_item(
+            MenuItem::FloatSlider {
+                id: MenuID::AttractorRadius(index),
+                current: attractor.radius,
+                min: 1.0,
+                max: 100.0,
+                step_size: 1.0,
+            },
+            format!("Attractor {number} radius"),
+        )
+        .add_menu_item(
+            MenuItem::FloatSlider {
+                id: MenuID::AttractorDepletion(index),
+                current: attractor.depletion,
+                min: 0.0,
+                max: 0.01,
+                step_size: 0.0005,
+            },
+            format!("Attractor {number} depletion"),
+        )
+        .add_menu_item(
+            MenuItem::Toggle {
+                id: MenuID::DeleteAttractor(index),
+                current: false,
+            },
+            format!(
+                "Delete attractor {number} at ({:.0}, {:.0})",
+                attractor.position.x, attractor.position.y
+            ),
+        );
+    }
     menu
 }
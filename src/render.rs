@@ -3,7 +3,8 @@
 //! # Rendering
 //!
 //! Supplies functionality to discretize and render the boids in the terminal
-//! using utf16 braille characters.
+//! using utf16 braille characters. The boids of the 3D simulation are
+//! projected onto the screen from the viewpoint of a [`Camera`].
 
 use std::io::{Result, Stdout};
 
@@ -16,10 +17,23 @@ use crossterm::{
 
 use crate::{
     SimulationSettings,
-    boids::{Boid, settings::BoidSettings},
+    boids::{
+        Attractor, Boid, BoidKind, Environment, FlightState, Flock3D, FoodSource, Obstacle, Path,
+        flow::{FLOW_CELL_SIZE, FlowField},
+        settings::BoidSettings,
+        walls::WallLayer,
+    },
+    vector2::Vector2,
+    vector3::Vector3,
 };
 
-/// Prints the boids in the terminal using braille characters as pixels.
+/// The bottom two dots of a braille character, drawn for perched boids.
+const PERCHED_BRAILLE: u8 = 0b1100_0000;
+
+/// Prints the boids, along with the obstacles, walls, paths, food, attractors
+/// and visible flow field in the `environment`, in the terminal using braille
+/// characters as pixels. The boids are drawn at `alpha` between their previous
+/// and current position, see [`interpolate`].
 ///
 /// # Errors
 ///
@@ -30,6 +44,8 @@ pub fn draw_boids<'a>(
     window_size: &WindowSize,
     sim_settings: &SimulationSettings,
     boid_settings: &BoidSettings,
+    environment: &Environment,
+    alpha: f32,
 ) -> Result<()> {
     queue!(stdout, SetColors(sim_settings.sim_color))?;
     let rows = window_size.rows;
@@ -41,9 +57,58 @@ pub fn draw_boids<'a>(
     let width_ratio: f32 = (columns as f32) / (boid_settings.width as f32);
     let height_ratio: f32 = (rows as f32) / (boid_settings.height as f32);
 
+    if environment.flow.visible && !environment.flow.is_empty() {
+        draw_flow(
+            &mut braille_grid,
+            &environment.flow,
+            (columns, rows),
+            (width_ratio, height_ratio),
+        );
+    }
+    for obstacle in environment.obstacles.iter() {
+        draw_obstacle(
+            &mut braille_grid,
+            obstacle,
+            (columns, rows),
+            (width_ratio, height_ratio),
+        );
+    }
+    for path in environment.paths.iter() {
+        draw_path(
+            &mut braille_grid,
+            path,
+            (columns, rows),
+            (width_ratio, height_ratio),
+        );
+    }
+    for food in environment.food.iter() {
+        draw_food(
+            &mut braille_grid,
+            food,
+            (columns, rows),
+            (width_ratio, height_ratio),
+        );
+    }
+    for attractor in environment.attractors.iter() {
+        draw_attractor(
+            &mut braille_grid,
+            attractor,
+            (columns, rows),
+            (width_ratio, height_ratio),
+        );
+    }
+    if !environment.walls.is_empty() {
+        draw_walls(
+            &mut braille_grid,
+            &environment.walls,
+            (columns, rows),
+            (width_ratio, height_ratio),
+        );
+    }
+
     for boid in boids {
         // Determine the boid's character position
-        let position = boid.position;
+        let position = interpolate(boid, alpha, boid_settings);
         let x = position.x * width_ratio;
         let c = x.floor();
         if c as u16 >= columns || c < 0.0 {
@@ -55,13 +120,228 @@ pub fn draw_boids<'a>(
             continue;
         }
 
-        // Braille based on position within character
-        let braille = pos_to_braille(x - c, y - r);
+        // Braille based on position within character, predators fill the
+        // entire character to stand out from the flock, while perched boids
+        // rest on the bottom of the character.
+        let braille = match (boid.kind, boid.flight) {
+            (BoidKind::Predator, _) => 0xFF,
+            (BoidKind::Prey, FlightState::Perched { .. }) => PERCHED_BRAILLE,
+            (BoidKind::Prey, FlightState::Flying) => pos_to_braille(x - c, y - r),
+        };
 
         // As braille is like binary, the boids can be or'ed to merge characters.
         braille_grid[(c as usize) + (r as usize) * (columns as usize)] |= braille;
     }
 
+    print_braille_grid(stdout, &braille_grid, (columns, rows))
+}
+
+/// Returns the position of the `boid` at `alpha` between its previous and
+/// current position, where boids that wrapped around the borders are drawn
+/// at their current position.
+fn interpolate(boid: &Boid, alpha: f32, boid_settings: &BoidSettings) -> Vector2 {
+    let diff = boid.position - boid.previous_position;
+    if diff.x.abs() * 2.0 > boid_settings.width as f32
+        || diff.y.abs() * 2.0 > boid_settings.height as f32
+    {
+        return boid.position;
+    }
+    boid.previous_position + diff * alpha
+}
+
+/// The viewpoint from which the 3D boids are projected onto the screen,
+/// looking at the center of their volume from any direction.
+pub struct Camera {
+    /// The rotation around the vertical axis in radians.
+    pub yaw: f32,
+    /// The rotation around the horizontal axis in radians.
+    pub pitch: f32,
+    /// The distance to the center of the volume, relative to the radius of
+    /// the sphere enclosing the volume.
+    pub distance: f32,
+    /// Whether the camera slowly orbits around the volume by itself.
+    pub orbiting: bool,
+}
+
+impl Camera {
+    // The focal length of the projection, relative to half of the screen.
+    const FOCAL_LENGTH: f32 = 2.0;
+    // The closest distance at which points are still projected.
+    const NEAR_PLANE: f32 = 0.1;
+    // The furthest the camera can look from above or below.
+    const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 * 0.95;
+    // The orbiting speed in radians per second.
+    const ORBIT_SPEED: f32 = 0.2;
+
+    /// Creates a new [`Camera`] at the given relative `distance`.
+    pub fn new(distance: f32, orbiting: bool) -> Camera {
+        Camera {
+            yaw: 0.0,
+            pitch: 0.0,
+            distance,
+            orbiting,
+        }
+    }
+
+    /// Rotates the camera around the volume by `yaw` and `pitch` radians,
+    /// where the pitch is limited to prevent the view from flipping.
+    pub fn rotate(&mut self, yaw: f32, pitch: f32) {
+        self.yaw = (self.yaw + yaw).rem_euclid(std::f32::consts::TAU);
+        self.pitch = (self.pitch + pitch).clamp(-Self::MAX_PITCH, Self::MAX_PITCH);
+    }
+
+    /// Multiplies the distance to the volume by the `factor`, staying outside
+    /// of the enclosing sphere.
+    pub fn zoom(&mut self, factor: f32) {
+        self.distance = (self.distance * factor).clamp(1.0 + Self::NEAR_PLANE, 10.0);
+    }
+
+    /// Orbits the camera for `delta` seconds of real time, if orbiting.
+    pub fn orbit(&mut self, delta: f32) {
+        if self.orbiting {
+            self.rotate(Self::ORBIT_SPEED * delta, 0.0);
+        }
+    }
+
+    /// Projects the `point` in a volume with the given `center` and `radius`
+    /// onto the screen using perspective. The projected point lies within the
+    /// unit circle when the enclosing sphere of the volume fits on the screen.
+    ///
+    /// # Return
+    /// Returns the projected point along with its depth, ranging from zero at
+    /// the near side of the enclosing sphere to one at the far side, or `None`
+    /// if the point lies behind the camera.
+    fn project(&self, point: Vector3, center: Vector3, radius: f32) -> Option<(Vector2, f32)> {
+        let diff = (point - center) / radius;
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        // Rotate around the vertical axis, followed by the horizontal axis.
+        let x = diff.x * cos_yaw - diff.z * sin_yaw;
+        let z = diff.x * sin_yaw + diff.z * cos_yaw;
+        let y = diff.y * cos_pitch - z * sin_pitch;
+        let z = diff.y * sin_pitch + z * cos_pitch + self.distance;
+        if z < Self::NEAR_PLANE {
+            return None;
+        }
+        let depth = ((z - self.distance + 1.0) / 2.0).clamp(0.0, 1.0);
+        Some((Vector2::new(x, y) * (Self::FOCAL_LENGTH / z), depth))
+    }
+}
+
+/// Prints the boids of the `flock` in the terminal, projected with the
+/// perspective of the `camera`, along with the edges of their volume. Boids
+/// closer to the camera are drawn with more dots, while the boids are drawn at
+/// `alpha` between their previous and current position.
+///
+/// # Errors
+///
+/// This function will return an error if it fails to queue its drawing operation.
+pub fn draw_boids_3d(
+    stdout: &mut Stdout,
+    flock: &Flock3D,
+    camera: &Camera,
+    window_size: &WindowSize,
+    sim_settings: &SimulationSettings,
+    alpha: f32,
+) -> Result<()> {
+    // Distance between the dots of the edges in world units.
+    const DOT_SPACING: f32 = 3.0;
+    // The dots drawn for a boid in the near, middle and far third of the volume.
+    const NEAR_DOTS: [(f32, f32); 4] = [(0.0, 0.0), (0.5, 0.0), (0.0, 0.25), (0.5, 0.25)];
+    const MIDDLE_DOTS: [(f32, f32); 2] = [(0.0, 0.0), (0.5, 0.0)];
+    const FAR_DOTS: [(f32, f32); 1] = [(0.0, 0.0)];
+
+    queue!(stdout, SetColors(sim_settings.sim_color))?;
+    let rows = window_size.rows;
+    let columns = window_size.columns;
+    let mut braille_grid = vec![0u8; (rows as usize) * (columns as usize)];
+
+    let center = flock.size / 2.0;
+    let radius = center.magnitude();
+    // Half of the smallest side of the screen in columns, as characters are
+    // half as wide as they are high.
+    let half_extent = (columns as f32).min(rows as f32 * 2.0) / 2.0;
+    let project = |point: Vector3| {
+        camera.project(point, center, radius).map(|(point, depth)| {
+            let x = columns as f32 / 2.0 + point.x * half_extent;
+            let y = (rows as f32 + point.y * half_extent) / 2.0;
+            (x, y, depth)
+        })
+    };
+
+    // Edges of the volume, between corners differing along a single axis.
+    for corner in 0..8 {
+        let start = Vector3::new(
+            (corner & 1) as f32,
+            (corner >> 1 & 1) as f32,
+            (corner >> 2 & 1) as f32,
+        ) * flock.size;
+        for axis in [1, 2, 4] {
+            if corner & axis != 0 {
+                continue;
+            }
+            let end = Vector3::new(
+                ((corner | axis) & 1) as f32,
+                ((corner | axis) >> 1 & 1) as f32,
+                ((corner | axis) >> 2 & 1) as f32,
+            ) * flock.size;
+            let diff = end - start;
+            let dots = (diff.magnitude() / DOT_SPACING).ceil() as usize;
+            for dot in 0..=dots {
+                if let Some((x, y, _)) = project(start + diff * (dot as f32 / dots as f32)) {
+                    plot(&mut braille_grid, x, y, (columns, rows));
+                }
+            }
+        }
+    }
+
+    for boid in flock.boids.iter_all() {
+        let position = boid.previous_position + (boid.position - boid.previous_position) * alpha;
+        let Some((x, y, depth)) = project(position) else {
+            continue;
+        };
+        let dots: &[(f32, f32)] = if depth < 1.0 / 3.0 {
+            &NEAR_DOTS
+        } else if depth < 2.0 / 3.0 {
+            &MIDDLE_DOTS
+        } else {
+            &FAR_DOTS
+        };
+        for (x_offset, y_offset) in dots {
+            plot(
+                &mut braille_grid,
+                x + x_offset,
+                y + y_offset,
+                (columns, rows),
+            );
+        }
+    }
+
+    print_braille_grid(stdout, &braille_grid, (columns, rows))
+}
+
+/// Or's the braille dot at the character coordinates `x` and `y` into the
+/// `braille_grid`, which is `size` columns wide and rows high, if it falls
+/// within the grid.
+fn plot(braille_grid: &mut [u8], x: f32, y: f32, (columns, rows): (u16, u16)) {
+    if x < 0.0 || y < 0.0 || x >= columns as f32 || y >= rows as f32 {
+        return;
+    }
+    let (c, r) = (x.floor(), y.floor());
+    braille_grid[(c as usize) + (r as usize) * (columns as usize)] |= pos_to_braille(x - c, y - r);
+}
+
+/// Prints the `braille_grid`, which is `size` columns wide and rows high, over
+/// the previous frame in the terminal.
+///
+/// # Errors
+///
+/// This function will return an error if it fails to queue its drawing operation.
+fn print_braille_grid(
+    stdout: &mut Stdout,
+    braille_grid: &[u8],
+    (columns, rows): (u16, u16),
+) -> Result<()> {
     // Generate the empty character, deleting the previous frame.
     let empty_line = " ".repeat(columns as usize);
 
@@ -94,6 +374,219 @@ pub fn draw_boids<'a>(
     Ok(())
 }
 
+/// Prints the `status` message on the bottom row of the terminal.
+///
+/// # Errors
+///
+/// This function will return an error if it fails to queue its drawing operation.
+pub fn draw_status(stdout: &mut Stdout, status: &str, window_size: &WindowSize) -> Result<()> {
+    let status: String = status.chars().take(window_size.columns as usize).collect();
+    queue!(
+        stdout,
+        MoveTo(0, window_size.rows.saturating_sub(1)),
+        Print(status)
+    )?;
+    Ok(())
+}
+
+/// Prints the name of the current mouse `tool` in the bottom right corner of
+/// the terminal.
+///
+/// # Errors
+///
+/// This function will return an error if it fails to queue its drawing operation.
+pub fn draw_tool_indicator(
+    stdout: &mut Stdout,
+    tool: &str,
+    window_size: &WindowSize,
+) -> Result<()> {
+    let indicator = format!("[{}]", tool);
+    let column = window_size.columns.saturating_sub(indicator.len() as u16);
+    queue!(
+        stdout,
+        MoveTo(column, window_size.rows.saturating_sub(1)),
+        Print(indicator)
+    )?;
+    Ok(())
+}
+
+/// Or's every braille dot covered by the `obstacle` into the `braille_grid`,
+/// which is `size` columns wide and rows high, where `ratios` convert world
+/// coordinates to character coordinates.
+fn draw_obstacle(
+    braille_grid: &mut [u8],
+    obstacle: &Obstacle,
+    (columns, rows): (u16, u16),
+    (width_ratio, height_ratio): (f32, f32),
+) {
+    let (min, max) = obstacle.bounds();
+    let first_column = (min.x * width_ratio).floor().max(0.0) as usize;
+    let last_column = ((max.x * width_ratio).ceil().max(0.0) as usize).min(columns as usize);
+    let first_row = (min.y * height_ratio).floor().max(0.0) as usize;
+    let last_row = ((max.y * height_ratio).ceil().max(0.0) as usize).min(rows as usize);
+    // Line segments are drawn a single character wide.
+    let thickness = 1.0 / width_ratio;
+
+    for r in first_row..last_row {
+        for c in first_column..last_column {
+            // Check each of the 2x4 dots in the character.
+            for dot in 0..8 {
+                let x_norm = ((dot % 2) as f32 + 0.5) / 2.0;
+                let y_norm = ((dot / 2) as f32 + 0.5) / 4.0;
+                let position = Vector2::new(
+                    (c as f32 + x_norm) / width_ratio,
+                    (r as f32 + y_norm) / height_ratio,
+                );
+                if obstacle.covers(position, thickness) {
+                    braille_grid[c + r * (columns as usize)] |= pos_to_braille(x_norm, y_norm);
+                }
+            }
+        }
+    }
+}
+
+/// Or's every braille dot falling within a wall of the `walls` into the
+/// `braille_grid`, see [`draw_obstacle`].
+fn draw_walls(
+    braille_grid: &mut [u8],
+    walls: &WallLayer,
+    (columns, rows): (u16, u16),
+    (width_ratio, height_ratio): (f32, f32),
+) {
+    for r in 0..rows as usize {
+        for c in 0..columns as usize {
+            for dot in 0..8 {
+                let x_norm = ((dot % 2) as f32 + 0.5) / 2.0;
+                let y_norm = ((dot / 2) as f32 + 0.5) / 4.0;
+                let position = Vector2::new(
+                    (c as f32 + x_norm) / width_ratio,
+                    (r as f32 + y_norm) / height_ratio,
+                );
+                if walls.is_wall(position) {
+                    braille_grid[c + r * (columns as usize)] |= pos_to_braille(x_norm, y_norm);
+                }
+            }
+        }
+    }
+}
+
+/// Or's a faint arrow for every other cell of the `flow` field into the
+/// `braille_grid`, see [`draw_obstacle`]. Each arrow is a few dots starting at
+/// the center of its cell, reaching further along the force the stronger it
+/// is, up to the size of the cell.
+fn draw_flow(
+    braille_grid: &mut [u8],
+    flow: &FlowField,
+    (columns, rows): (u16, u16),
+    (width_ratio, height_ratio): (f32, f32),
+) {
+    // The amount of dots along each arrow.
+    const DOTS: usize = 3;
+    // The cells between the arrows along each direction.
+    const SPACING: usize = 2;
+    let max_force = (0..flow.rows)
+        .flat_map(|row| (0..flow.columns).map(move |column| (row, column)))
+        .map(|(row, column)| flow.cell_force(row, column).magnitude())
+        .fold(0.0, f32::max);
+    if max_force == 0.0 {
+        return;
+    }
+    for row in (0..flow.rows).step_by(SPACING) {
+        for column in (0..flow.columns).step_by(SPACING) {
+            let center = FlowField::cell_center(row, column);
+            let arrow = flow.cell_force(row, column) * (FLOW_CELL_SIZE / max_force);
+            for dot in 0..DOTS {
+                let position = center + arrow * (dot as f32 / (DOTS - 1) as f32);
+                plot(
+                    braille_grid,
+                    position.x * width_ratio,
+                    position.y * height_ratio,
+                    (columns, rows),
+                );
+            }
+        }
+    }
+}
+
+/// Or's a dotted line along each segment of the `path` into the
+/// `braille_grid`, see [`draw_obstacle`].
+fn draw_path(
+    braille_grid: &mut [u8],
+    path: &Path,
+    (columns, rows): (u16, u16),
+    (width_ratio, height_ratio): (f32, f32),
+) {
+    // Distance between the dots of the line in world units.
+    const DOT_SPACING: f32 = 2.0;
+    for (start, end) in path.segments() {
+        let diff = end - start;
+        let dots = (diff.magnitude() / DOT_SPACING).ceil() as usize;
+        for dot in 0..=dots {
+            let position = start + diff * (dot as f32 / dots.max(1) as f32);
+            plot(
+                braille_grid,
+                position.x * width_ratio,
+                position.y * height_ratio,
+                (columns, rows),
+            );
+        }
+    }
+}
+
+/// Or's a dotted circle around the `food` source into the `braille_grid`, see
+/// [`draw_obstacle`].
+fn draw_food(
+    braille_grid: &mut [u8],
+    food: &FoodSource,
+    (columns, rows): (u16, u16),
+    (width_ratio, height_ratio): (f32, f32),
+) {
+    // Distance between the dots of the circle in world units.
+    const DOT_SPACING: f32 = 2.0;
+    let dots = ((std::f32::consts::TAU * food.radius) / DOT_SPACING).ceil() as usize;
+    for dot in 0..dots {
+        let angle = std::f32::consts::TAU * dot as f32 / dots as f32;
+        let position = food.position + Vector2::new(angle.cos(), angle.sin()) * food.radius;
+        plot(
+            braille_grid,
+            position.x * width_ratio,
+            position.y * height_ratio,
+            (columns, rows),
+        );
+    }
+}
+
+/// Or's a dotted circle along the radius of the `attractor` into the
+/// `braille_grid`, see [`draw_obstacle`]. Depleted attractors only show their
+/// center.
+fn draw_attractor(
+    braille_grid: &mut [u8],
+    attractor: &Attractor,
+    (columns, rows): (u16, u16),
+    (width_ratio, height_ratio): (f32, f32),
+) {
+    // Distance between the dots of the circle in world units.
+    const DOT_SPACING: f32 = 2.0;
+    let dots = if attractor.is_active() {
+        ((std::f32::consts::TAU * attractor.radius) / DOT_SPACING).ceil() as usize
+    } else {
+        0
+    };
+    let center = std::iter::once(attractor.position);
+    let circle = (0..dots).map(|dot| {
+        let angle = std::f32::consts::TAU * dot as f32 / dots as f32;
+        attractor.position + Vector2::new(angle.cos(), angle.sin()) * attractor.radius
+    });
+    for position in center.chain(circle) {
+        plot(
+            braille_grid,
+            position.x * width_ratio,
+            position.y * height_ratio,
+            (columns, rows),
+        );
+    }
+}
+
 /// Returns the least significant part of the brailles utf16 based on where in
 /// the character the point is present.
 ///
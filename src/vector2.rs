@@ -39,7 +39,7 @@
 
 use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Vector2 {
     pub x: f32,
     pub y: f32,
@@ -70,6 +70,18 @@ impl Vector2 {
         self.x * self.x + self.y * self.y
     }
 
+    /// Returns the vector scaled to unit length, or the zero vector if the
+    /// vector has no length.
+    #[inline]
+    pub fn normalised(&self) -> Vector2 {
+        let magnitude = self.magnitude();
+        if magnitude > 0.0 {
+            *self / magnitude
+        } else {
+            Vector2::ZERO
+        }
+    }
+
     /// Dot product of vector with other
     #[inline]
     #[allow(dead_code)]
@@ -0,0 +1,201 @@
+//! Creation and manipulation of a 3D f32 vector.
+//!
+//! # Vector3
+//! Contains the 3D counterpart of [`Vector2`](crate::vector2::Vector2), used by
+//! the 3D simulation of the boids.
+//!
+//! ## Operations
+//! The vector implements the same operations as the 2D vector, such as
+//! addition, multiplication and division.
+//!
+//! # Examples
+//! ```
+//! let mut v1: Vector3 = Vector3::new(1.0, 0.0, 0.0);
+//! let v2: Vector3 = Vector3::new(0.0, 1.0, 0.0);
+//!
+//! let v3 = v1 - v2;
+//! assert_eq!(v3.sqr_magnitude(), 2.0);
+//!
+//! v1 += v2 * 2f32;
+//! assert_eq!(v1.y, 2.0);
+//! ```
+
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Vector3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Vector3 {
+    /// Constant defining the zero vector
+    pub const ZERO: Vector3 = Vector3 {
+        x: 0f32,
+        y: 0f32,
+        z: 0f32,
+    };
+
+    /// Creates a new [`Vector3`].
+    #[inline]
+    pub fn new(x: f32, y: f32, z: f32) -> Self {
+        Vector3 { x, y, z }
+    }
+
+    /// Magnitude of vector
+    #[inline]
+    pub fn magnitude(&self) -> f32 {
+        f32::sqrt(self.sqr_magnitude())
+    }
+
+    /// Square magnitude of vector
+    #[inline]
+    pub fn sqr_magnitude(&self) -> f32 {
+        self.x * self.x + self.y * self.y + self.z * self.z
+    }
+
+    /// Returns the vector scaled to unit length, or the zero vector if the
+    /// vector has no length.
+    #[inline]
+    pub fn normalised(&self) -> Vector3 {
+        let magnitude = self.magnitude();
+        if magnitude > 0.0 {
+            *self / magnitude
+        } else {
+            Vector3::ZERO
+        }
+    }
+}
+
+impl Add for Vector3 {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
+impl AddAssign for Vector3 {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+        self.z += rhs.z;
+    }
+}
+
+impl Sub for Vector3 {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+        }
+    }
+}
+
+impl SubAssign for Vector3 {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Self) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+        self.z -= rhs.z;
+    }
+}
+
+impl Mul for Vector3 {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x * rhs.x,
+            y: self.y * rhs.y,
+            z: self.z * rhs.z,
+        }
+    }
+}
+
+impl MulAssign for Vector3 {
+    #[inline]
+    fn mul_assign(&mut self, rhs: Self) {
+        self.x *= rhs.x;
+        self.y *= rhs.y;
+        self.z *= rhs.z;
+    }
+}
+
+impl Mul<f32> for Vector3 {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: f32) -> Self::Output {
+        Self {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+        }
+    }
+}
+
+impl MulAssign<f32> for Vector3 {
+    #[inline]
+    fn mul_assign(&mut self, rhs: f32) {
+        self.x *= rhs;
+        self.y *= rhs;
+        self.z *= rhs;
+    }
+}
+
+impl Div for Vector3 {
+    type Output = Self;
+
+    #[inline]
+    fn div(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x / rhs.x,
+            y: self.y / rhs.y,
+            z: self.z / rhs.z,
+        }
+    }
+}
+
+impl DivAssign for Vector3 {
+    #[inline]
+    fn div_assign(&mut self, rhs: Self) {
+        self.x /= rhs.x;
+        self.y /= rhs.y;
+        self.z /= rhs.z;
+    }
+}
+
+impl Div<f32> for Vector3 {
+    type Output = Self;
+
+    #[inline]
+    fn div(self, rhs: f32) -> Self::Output {
+        Self {
+            x: self.x / rhs,
+            y: self.y / rhs,
+            z: self.z / rhs,
+        }
+    }
+}
+
+impl DivAssign<f32> for Vector3 {
+    #[inline]
+    fn div_assign(&mut self, rhs: f32) {
+        self.x /= rhs;
+        self.y /= rhs;
+        self.z /= rhs;
+    }
+}